@@ -0,0 +1,63 @@
+use core::{alloc::Layout, mem::{align_of_val, size_of_val}, ptr};
+#[cfg(not(feature = "std"))]
+use ::alloc::boxed::Box;
+use ::ptr_meta::Pointee;
+
+/// Copies the bytes of a possibly-unsized value into a new box with the same size, alignment and
+/// metadata.
+///
+/// This is the building block used to move accessors out of their versioned value and into their
+/// own allocation. Zero-sized values do not allocate. The result is a plain `Box` because a box of
+/// a value is already allocated with the value's alignment, so there is no separate aligned box
+/// type.
+///
+/// # Safety
+///
+/// The returned box owns a bitwise copy of `value`. Unless the value is `Copy`, the caller must
+/// make sure that the original value is not dropped or otherwise used after this is called.
+pub unsafe fn copy_to_box<U: Pointee + ?Sized>(value: &U) -> Box<U> {
+    // SAFETY: the size and alignment of a value always form a valid layout
+    let layout = Layout::from_size_align_unchecked(size_of_val(value), align_of_val(value));
+    // SAFETY: value is valid for reads of layout.size() bytes
+    let ptr = copy_to_alloc((value as *const U).cast::<u8>(), layout);
+    let boxed_ptr = ::ptr_meta::from_raw_parts_mut::<U>(ptr.cast(), ::ptr_meta::metadata(value));
+    // SAFETY: boxed_ptr was allocated with the layout of the value it points to
+    Box::from_raw(boxed_ptr)
+}
+
+/// Copies `layout.size()` bytes from `src` into a new allocation with the given layout.
+///
+/// This is kept separate from the generic functions that call it so that it is only compiled
+/// once.
+///
+/// If `layout` has a size of zero, no memory is allocated and a dangling pointer aligned to
+/// `layout.align()` is returned instead.
+///
+/// # Safety
+///
+/// - `src` must be valid for reads of `layout.size()` bytes.
+/// - The caller takes ownership of the allocation, and must deallocate it with the same `layout`,
+///   for example by turning it into a `Box` of a value with that layout. The dangling pointer
+///   returned for a zero-sized layout must not be deallocated.
+unsafe fn copy_to_alloc(src: *const u8, layout: Layout) -> *mut u8 {
+    #[cfg(feature = "std")]
+    use ::std::alloc::{alloc, handle_alloc_error};
+    #[cfg(not(feature = "std"))]
+    use ::alloc::alloc::{alloc, handle_alloc_error};
+
+    if layout.size() == 0 {
+        // SAFETY: layout.align() is non-zero
+        ptr::NonNull::new_unchecked(layout.align() as *mut u8).as_ptr()
+    } else {
+        // SAFETY: layout has non-zero size
+        let ptr = alloc(layout);
+        if ptr.is_null() {
+            handle_alloc_error(layout);
+        }
+        // SAFETY:
+        // - src is valid for reads
+        // - ptr is valid for writes
+        ptr::copy_nonoverlapping(src, ptr, layout.size());
+        ptr
+    }
+}
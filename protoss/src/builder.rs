@@ -0,0 +1,44 @@
+use core::fmt;
+
+/// An error that occurred while building a versioned value for a target version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// The target version is not a version of the type.
+    UnknownVersion {
+        /// The target version.
+        version: u16,
+    },
+    /// A field was set that was added after the target version.
+    FieldTooNew {
+        /// The name of the field.
+        name: &'static str,
+        /// The version the field was added in.
+        version: u16,
+        /// The target version.
+        target: u16,
+    },
+    /// A field required by the target version was not set.
+    MissingField {
+        /// The name of the field.
+        name: &'static str,
+    },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownVersion { version } => write!(f, "unknown target version {}", version),
+            Self::FieldTooNew { name, version, target } => write!(
+                f,
+                "field `{}` was added in version {} and cannot be set for target version {}",
+                name,
+                version,
+                target,
+            ),
+            Self::MissingField { name } => write!(f, "missing field `{}`", name),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BuildError {}
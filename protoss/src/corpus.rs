@@ -0,0 +1,155 @@
+use std::{
+    fs,
+    io,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    string::String,
+    vec::Vec,
+};
+
+/// A directory of sample archives, recorded once for each version of each versioned type.
+///
+/// A corpus is used to check that archives written by released versions can still be read. Each
+/// archive is stored at `<root>/<name>/v<version>.bin`, so a corpus can be checked into version
+/// control alongside the types it covers.
+pub struct Corpus {
+    root: PathBuf,
+}
+
+/// An archive stored in a [`Corpus`].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CorpusEntry {
+    /// The name the archive was recorded under.
+    pub name: String,
+    /// The version of the archive.
+    pub version: u16,
+    /// The path to the archive.
+    pub path: PathBuf,
+}
+
+impl CorpusEntry {
+    /// Reads the bytes of the archive.
+    #[inline]
+    pub fn read(&self) -> io::Result<Vec<u8>> {
+        fs::read(&self.path)
+    }
+}
+
+impl Corpus {
+    /// Creates a corpus stored in the given directory.
+    ///
+    /// The directory is created when the first archive is recorded.
+    #[inline]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+        }
+    }
+
+    /// Returns the directory the corpus is stored in.
+    #[inline]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, name: &str, version: u16) -> io::Result<PathBuf> {
+        let is_valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !is_valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "corpus names must be nonempty and contain only ASCII letters, digits, `_` and `-`",
+            ));
+        }
+
+        Ok(self.root.join(name).join(format!("v{}.bin", version)))
+    }
+
+    /// Records an archive under the given name and version, replacing any existing archive.
+    pub fn record(&self, name: &str, version: u16, bytes: &[u8]) -> io::Result<()> {
+        let path = self.path(name, version)?;
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, bytes)
+    }
+
+    /// Reads the archive recorded under the given name and version, if there is one.
+    pub fn get(&self, name: &str, version: u16) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(name, version)?) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns all of the archives in the corpus, sorted by name and then version.
+    ///
+    /// Files that do not follow the corpus layout are ignored.
+    pub fn iter(&self) -> io::Result<impl Iterator<Item = CorpusEntry>> {
+        let mut entries = Vec::new();
+
+        let names = match fs::read_dir(&self.root) {
+            Ok(names) => names,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(entries.into_iter()),
+            Err(e) => return Err(e),
+        };
+        for name_entry in names {
+            let name_entry = name_entry?;
+            if !name_entry.file_type()?.is_dir() {
+                continue;
+            }
+            let name = match name_entry.file_name().into_string() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+
+            for version_entry in fs::read_dir(name_entry.path())? {
+                let version_entry = version_entry?;
+                let file_name = version_entry.file_name();
+                let version = file_name.to_str()
+                    .and_then(|file_name| file_name.strip_prefix('v'))
+                    .and_then(|file_name| file_name.strip_suffix(".bin"))
+                    .and_then(|version| version.parse::<u16>().ok());
+                if let Some(version) = version {
+                    entries.push(CorpusEntry {
+                        name: name.clone(),
+                        version,
+                        path: version_entry.path(),
+                    });
+                }
+            }
+        }
+
+        entries.sort();
+        Ok(entries.into_iter())
+    }
+}
+
+/// Replays every input stored in a directory, such as the crashes and timeouts found by a fuzzer.
+///
+/// Each file in the directory is read and passed to `f` in order of file name, and hidden files
+/// are skipped. `f` should run the inputs through the same paths as the fuzz target, so that
+/// calling this from a `#[test]` turns fuzz findings into regression tests. Returns the number of
+/// inputs that were replayed.
+///
+/// # Panics
+///
+/// Panics with the path of the input if `f` panics while replaying it.
+pub fn replay_corpus(dir: impl AsRef<Path>, mut f: impl FnMut(&[u8])) -> io::Result<usize> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if entry.file_type()?.is_file() && !is_hidden {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+
+    for path in paths.iter() {
+        let bytes = fs::read(path)?;
+        if catch_unwind(AssertUnwindSafe(|| f(&bytes))).is_err() {
+            panic!("replaying corpus input {} panicked", path.display());
+        }
+    }
+
+    Ok(paths.len())
+}
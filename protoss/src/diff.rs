@@ -0,0 +1,45 @@
+use core::fmt;
+
+/// A field that differs between two accessors of the same versioned type.
+#[derive(Clone, Copy)]
+pub struct FieldDiff<'a> {
+    /// The name of the field.
+    pub name: &'static str,
+    /// The version the field was added in.
+    pub version: u16,
+    /// The value of the field in the first accessor, or `None` if the field is absent.
+    pub left: Option<&'a dyn fmt::Debug>,
+    /// The value of the field in the second accessor, or `None` if the field is absent.
+    pub right: Option<&'a dyn fmt::Debug>,
+}
+
+impl FieldDiff<'_> {
+    /// Returns whether the field is present in both accessors with different values.
+    #[inline]
+    pub fn is_changed(&self) -> bool {
+        self.left.is_some() && self.right.is_some()
+    }
+
+    /// Returns whether the field is only present in the first accessor.
+    #[inline]
+    pub fn is_left_only(&self) -> bool {
+        self.left.is_some() && self.right.is_none()
+    }
+
+    /// Returns whether the field is only present in the second accessor.
+    #[inline]
+    pub fn is_right_only(&self) -> bool {
+        self.left.is_none() && self.right.is_some()
+    }
+}
+
+impl fmt::Debug for FieldDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FieldDiff")
+            .field("name", &self.name)
+            .field("version", &self.version)
+            .field("left", &self.left)
+            .field("right", &self.right)
+            .finish()
+    }
+}
@@ -0,0 +1,67 @@
+/// The location of a single field within a versioned type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldLayout {
+    /// The name of the field.
+    pub name: &'static str,
+    /// The offset of the field from the start of the versioned type.
+    pub offset: usize,
+    /// The size of the field in bytes.
+    pub size: usize,
+}
+
+/// A single version of a versioned type.
+///
+/// The versions of a type are returned by the `evolution_history` function generated by the
+/// `protoss` attribute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VersionInfo {
+    /// The version number.
+    pub version: u16,
+    /// The size of an accessor for the version in bytes.
+    pub size: usize,
+    /// The names of the fields in the version, including the fields of all earlier versions.
+    pub fields: &'static [&'static str],
+}
+
+/// A versioned type that can describe the layout of its fields.
+///
+/// This is implemented by the `protoss` attribute.
+///
+/// # Wire order
+///
+/// Fields are ordered by the version they were added in, and then by their `#[field(id)]` within a
+/// version, or by declaration if the version has no ids. This is the order of the fields in memory
+/// and in archives, and it can differ from the order the fields are declared in when
+/// `since_minor_version` or ids are used.
+pub trait DescribeFields {
+    /// The layouts of the fields, in [wire order](DescribeFields#wire-order).
+    const FIELDS: &'static [FieldLayout];
+}
+
+/// Asserts that `B` is a valid evolution of `A`.
+///
+/// Every field of `A` must appear in `B` in the same position, with the same name, offset and size.
+/// This is intended for tests and debug builds, and supplements the compile-time checks for pairs
+/// of types that are defined in different crates.
+///
+/// # Panics
+///
+/// Panics with a description of the first mismatch if `B` is not a valid evolution of `A`.
+pub fn assert_prefix_compatible<A: DescribeFields + ?Sized, B: DescribeFields + ?Sized>() {
+    assert!(
+        A::FIELDS.len() <= B::FIELDS.len(),
+        "evolution has {} fields but the original has {}",
+        B::FIELDS.len(),
+        A::FIELDS.len(),
+    );
+
+    for (index, (a, b)) in A::FIELDS.iter().zip(B::FIELDS.iter()).enumerate() {
+        assert!(
+            a == b,
+            "field {} is incompatible: original is {:?} but evolution is {:?}",
+            index,
+            a,
+            b,
+        );
+    }
+}
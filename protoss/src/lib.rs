@@ -3,18 +3,121 @@
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
+mod boxed;
+mod builder;
+#[cfg(feature = "std")]
+mod corpus;
+mod diff;
+mod layout;
+#[cfg(feature = "std")]
+mod log;
+mod macros;
 mod proto;
+mod provenance;
+#[cfg(feature = "std")]
+mod render;
+#[cfg(feature = "rkyv")]
+mod rkyv;
+mod small;
+mod visit;
 
 use ::ptr_meta::Pointee;
+pub use boxed::*;
+pub use builder::*;
+#[cfg(feature = "std")]
+pub use corpus::*;
+pub use diff::*;
+pub use layout::*;
+#[cfg(feature = "std")]
+pub use log::*;
 pub use proto::*;
+pub use provenance::*;
+#[cfg(feature = "std")]
+pub use render::*;
+#[cfg(feature = "rkyv")]
+pub use self::rkyv::*;
+pub use small::*;
+pub use visit::*;
 pub use protoss_derive::protoss;
 
+#[doc(hidden)]
+pub mod __private {
+    pub use ::ptr_meta;
+    /// Whether the `strict` feature is enabled. This is checked by generated code, which is
+    /// compiled with the features of the crate that uses it.
+    pub const STRICT: bool = cfg!(feature = "strict");
+    #[cfg(feature = "std")]
+    pub use ::std::vec::Vec;
+    #[cfg(not(feature = "std"))]
+    pub use ::alloc::vec::Vec;
+
+    use crate::VersionInfo;
+
+    const fn str_eq(a: &str, b: &str) -> bool {
+        let a = a.as_bytes();
+        let b = b.as_bytes();
+        if a.len() != b.len() {
+            return false;
+        }
+        let mut i = 0;
+        while i < a.len() {
+            if a[i] != b[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    pub const fn versions_eq(history: &[VersionInfo], versions: &[u16]) -> bool {
+        if history.len() != versions.len() {
+            return false;
+        }
+        let mut i = 0;
+        while i < history.len() {
+            if history[i].version != versions[i] {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Returns whether each version in the history adds exactly the given fields, in order.
+    pub const fn fields_added_eq(history: &[VersionInfo], added: &[&[&str]]) -> bool {
+        if history.len() != added.len() {
+            return false;
+        }
+        let mut previous = 0;
+        let mut i = 0;
+        while i < history.len() {
+            let fields = history[i].fields;
+            if fields.len() != previous + added[i].len() {
+                return false;
+            }
+            let mut j = 0;
+            while j < added[i].len() {
+                if !str_eq(fields[previous + j], added[i][j]) {
+                    return false;
+                }
+                j += 1;
+            }
+            previous = fields.len();
+            i += 1;
+        }
+        true
+    }
+}
+
 /// A type that has multiple versions that may be changed over time.
 ///
 /// # Safety
 ///
-/// `accessor_metadata` must return valid metadata to construct an `Accessor` using a pointer to
-/// the given version of this type.
+/// - `accessor_metadata` must return valid metadata to construct an `Accessor` using a pointer to
+///   the given version of this type.
+/// - `Accessor` must have an alignment at least as large as the alignment of this type, so that
+///   accessors copied into their own allocations keep their fields properly aligned.
+/// - `Accessor` must only be `Send` or `Sync` if this type is, since it owns the same fields.
 pub unsafe trait Versioned {
     /// The type that can be used to access the versioned data.
     type Accessor: Pointee + ?Sized;
@@ -28,3 +131,21 @@ pub unsafe trait Versioned {
     /// Returns the metadata of an `Accessor` for the given version.
     fn accessor_metadata(version: Self::Version) -> <Self::Accessor as Pointee>::Metadata;
 }
+
+/// A struct that holds the fields added in one version of a versioned type.
+///
+/// This is implemented by the `protoss` attribute for each version struct it generates, so a
+/// version can be named by its type instead of its number.
+pub trait VersionOf {
+    /// The versioned type this is a version of.
+    type Versioned: Versioned + ?Sized;
+
+    /// The version this struct holds the fields of.
+    const VERSION: <Self::Versioned as Versioned>::Version;
+}
+
+/// Returns the accessor metadata for the version `V`.
+#[inline]
+pub fn metadata_for<V: VersionOf + ?Sized>() -> <<V::Versioned as Versioned>::Accessor as Pointee>::Metadata {
+    V::Versioned::accessor_metadata(V::VERSION)
+}
@@ -0,0 +1,678 @@
+use core::{convert::TryFrom, fmt};
+use std::{error::Error, io::{self, Write}};
+#[cfg(feature = "rkyv")]
+use ::rkyv::{AlignedVec, Archive, ArchiveUnsized, Archived};
+#[cfg(feature = "rkyv")]
+use crate::{checked_unsized_root_in_order, CheckMetadata, MetadataError};
+
+/// The alignment of every record header and payload in a log, relative to the start of the log.
+pub const LOG_ALIGNMENT: usize = 16;
+
+/// The size of a [`RecordHeader`] in bytes.
+pub const LOG_HEADER_SIZE: usize = 16;
+
+/// The version of the log format written by [`LogWriter`].
+///
+/// Readers reject records with any other format version, so that the framing itself can change
+/// in the future without being misread.
+pub const LOG_FORMAT_VERSION: u8 = 0;
+
+/// The flag set in a record header when the payload was written by a big-endian producer.
+///
+/// All other flag bits are reserved and must be zero.
+const BIG_ENDIAN_FLAG: u8 = 1;
+
+/// The flag set in a record header when it has a checksum of the payload.
+const CHECKSUM_FLAG: u8 = 2;
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { 0xedb8_8320 ^ (crc >> 1) } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Computes the CRC-32 (IEEE) checksum of the given bytes.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, byte| CRC32_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8))
+}
+
+/// The byte order of a record payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    /// The payload was written by a little-endian producer.
+    Little,
+    /// The payload was written by a big-endian producer.
+    Big,
+}
+
+impl Endianness {
+    /// The byte order of the current target.
+    #[cfg(target_endian = "little")]
+    pub const NATIVE: Self = Self::Little;
+    /// The byte order of the current target.
+    #[cfg(target_endian = "big")]
+    pub const NATIVE: Self = Self::Big;
+
+    /// Returns whether this is the byte order of the current target.
+    #[inline]
+    pub fn is_native(&self) -> bool {
+        *self == Self::NATIVE
+    }
+
+    /// Returns the byte order that archived data is written in.
+    ///
+    /// This is the byte order selected by rkyv's `archive_le` and `archive_be` features, or the
+    /// byte order of the current target if neither is enabled.
+    #[cfg(feature = "rkyv")]
+    pub fn archived() -> Self {
+        let mut archived = core::mem::MaybeUninit::<Archived<u16>>::uninit();
+        let bytes = unsafe {
+            // SAFETY: resolving a u16 fully initializes its archived form, which is two bytes
+            1u16.resolve(0, (), archived.as_mut_ptr());
+            *archived.as_ptr().cast::<[u8; 2]>()
+        };
+        if bytes == 1u16.to_le_bytes() {
+            Self::Little
+        } else {
+            Self::Big
+        }
+    }
+
+    /// Returns the byte order that archived data is written in.
+    ///
+    /// Without rkyv, this is the byte order of the current target.
+    #[cfg(not(feature = "rkyv"))]
+    #[inline]
+    pub fn archived() -> Self {
+        Self::NATIVE
+    }
+
+    /// Reads an archived primitive that was written in this byte order.
+    ///
+    /// The bytes of the value are swapped if this is not the byte order that archived data is
+    /// written in by the current build, so producers with either byte order can be read by the
+    /// same consumer. Only primitives are swapped, so the root of a foreign payload must be accessed
+    /// with [`LogRecord::checked_root`], which reads the root pointer in the byte order of the
+    /// record.
+    #[inline]
+    pub fn read<T: SwapBytes>(&self, value: &T) -> T {
+        if *self == Self::archived() {
+            *value
+        } else {
+            value.swap_bytes()
+        }
+    }
+}
+
+/// A primitive that can be converted between byte orders by reversing its bytes.
+pub trait SwapBytes: Copy {
+    /// Returns the value with the order of its bytes reversed.
+    fn swap_bytes(self) -> Self;
+}
+
+macro_rules! impl_swap_bytes {
+    ($($ty:ty),*) => {
+        $(
+            impl SwapBytes for $ty {
+                #[inline]
+                fn swap_bytes(self) -> Self {
+                    <$ty>::swap_bytes(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_swap_bytes!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl SwapBytes for f32 {
+    #[inline]
+    fn swap_bytes(self) -> Self {
+        Self::from_bits(self.to_bits().swap_bytes())
+    }
+}
+
+impl SwapBytes for f64 {
+    #[inline]
+    fn swap_bytes(self) -> Self {
+        Self::from_bits(self.to_bits().swap_bytes())
+    }
+}
+
+impl SwapBytes for bool {
+    #[inline]
+    fn swap_bytes(self) -> Self {
+        self
+    }
+}
+
+/// The header written before every record payload.
+///
+/// A header is encoded as the payload length as a little-endian `u32`, the version as a
+/// little-endian `u16`, a flags byte recording the byte order of the payload and whether there is a
+/// checksum, the [`LOG_FORMAT_VERSION`], the optional CRC-32 of the payload as a little-endian
+/// `u32`, and reserved bytes which must be zero. The header itself is always little-endian so that
+/// it can be read by any consumer.
+///
+/// Readers reject headers with unknown flags or nonzero reserved bytes, and payloads that do not
+/// match their checksum. Records without a checksum are only checked for their framing, so
+/// corruption inside their payloads is not detected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordHeader {
+    /// The version of the record.
+    pub version: u16,
+    /// The length of the payload in bytes.
+    pub len: u32,
+    /// The byte order of the payload.
+    pub endianness: Endianness,
+    /// The CRC-32 (IEEE) checksum of the payload, if the record has one.
+    pub checksum: Option<u32>,
+}
+
+impl RecordHeader {
+    /// Returns this header with the checksum of the given payload.
+    #[inline]
+    pub fn with_checksum(mut self, payload: &[u8]) -> Self {
+        self.checksum = Some(crc32(payload));
+        self
+    }
+
+    /// Returns whether the given payload has the length and checksum recorded in this header.
+    #[inline]
+    pub fn matches(&self, payload: &[u8]) -> bool {
+        self.len as usize == payload.len() && self.checksum.is_none_or(|checksum| checksum == crc32(payload))
+    }
+
+    /// Returns the encoded bytes of the header.
+    #[inline]
+    pub fn to_bytes(&self) -> [u8; LOG_HEADER_SIZE] {
+        let mut result = [0u8; LOG_HEADER_SIZE];
+        result[0..4].copy_from_slice(&self.len.to_le_bytes());
+        result[4..6].copy_from_slice(&self.version.to_le_bytes());
+        if self.endianness == Endianness::Big {
+            result[6] |= BIG_ENDIAN_FLAG;
+        }
+        result[7] = LOG_FORMAT_VERSION;
+        if let Some(checksum) = self.checksum {
+            result[6] |= CHECKSUM_FLAG;
+            result[8..12].copy_from_slice(&checksum.to_le_bytes());
+        }
+        result
+    }
+
+    /// Writes the encoded header to the given sink.
+    #[inline]
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    /// Parses a header from its encoded bytes.
+    ///
+    /// Returns `None` if the format version is not [`LOG_FORMAT_VERSION`], any unknown flags are
+    /// set, or any of the reserved bytes are nonzero. The checksum bytes are reserved when the
+    /// checksum flag is not set.
+    pub fn parse(bytes: &[u8; LOG_HEADER_SIZE]) -> Option<Self> {
+        let has_checksum = bytes[6] & CHECKSUM_FLAG != 0;
+        let reserved = if has_checksum { &bytes[12..] } else { &bytes[8..] };
+        if Self::format_version(bytes) != LOG_FORMAT_VERSION
+            || bytes[6] & !(BIG_ENDIAN_FLAG | CHECKSUM_FLAG) != 0
+            || reserved.iter().any(|b| *b != 0)
+        {
+            None
+        } else {
+            Some(Self {
+                version: u16::from_le_bytes([bytes[4], bytes[5]]),
+                len: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+                endianness: if bytes[6] & BIG_ENDIAN_FLAG != 0 {
+                    Endianness::Big
+                } else {
+                    Endianness::Little
+                },
+                checksum: has_checksum.then(|| u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]])),
+            })
+        }
+    }
+
+    /// Returns the format version of an encoded header.
+    #[inline]
+    pub fn format_version(bytes: &[u8; LOG_HEADER_SIZE]) -> u8 {
+        bytes[7]
+    }
+
+    /// Returns the number of padding bytes written after the payload so that the next header is
+    /// aligned to [`LOG_ALIGNMENT`].
+    #[inline]
+    pub fn padding(&self) -> usize {
+        (LOG_ALIGNMENT - self.len as usize % LOG_ALIGNMENT) % LOG_ALIGNMENT
+    }
+}
+
+/// Appends version-framed records to a byte sink.
+///
+/// Each record is written as a [`RecordHeader`] followed by the payload, padded
+/// with zeroes so that the next header starts on a [`LOG_ALIGNMENT`] boundary. Records in the
+/// same log may have different versions, and are marked with the byte order of the producer.
+pub struct LogWriter<W: Write> {
+    inner: W,
+    pos: usize,
+    checksums: bool,
+}
+
+impl<W: Write> LogWriter<W> {
+    /// Creates a new log writer that writes to the start of the given sink.
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self::with_pos(inner, 0)
+    }
+
+    /// Creates a new log writer for a sink that already has `pos` bytes written to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is not a multiple of [`LOG_ALIGNMENT`].
+    #[inline]
+    pub fn with_pos(inner: W, pos: usize) -> Self {
+        assert!(pos & (LOG_ALIGNMENT - 1) == 0, "log position must be a multiple of LOG_ALIGNMENT");
+        Self { inner, pos, checksums: false }
+    }
+
+    /// Sets whether to write a checksum of each appended payload to its header.
+    ///
+    /// Readers verify the checksums of records that have them, so this detects corrupted payloads
+    /// at the cost of computing a CRC-32 on both sides. Records appended with
+    /// [`append_record`](Self::append_record) keep the checksum they were read with.
+    #[inline]
+    pub fn with_checksums(mut self, checksums: bool) -> Self {
+        self.checksums = checksums;
+        self
+    }
+
+    /// Returns the number of bytes written to the sink so far.
+    #[inline]
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Appends a record with the given version and payload, returning the position of the
+    /// payload in the log.
+    ///
+    /// The payload is usually the bytes of an archived versioned value, and is marked with the
+    /// byte order that archived data is written in.
+    pub fn append(&mut self, version: u16, payload: &[u8]) -> io::Result<usize> {
+        let checksum = self.checksums.then(|| crc32(payload));
+        self.append_with_endianness(version, Endianness::archived(), checksum, payload)
+    }
+
+    /// Appends a record read from another log, returning the position of the payload in this log.
+    ///
+    /// Only a new header is encoded; the payload is copied directly from the original log, so it
+    /// keeps the byte order of the producer that wrote it. This re-frames records without
+    /// accessing or re-serializing them.
+    #[inline]
+    pub fn append_record(&mut self, record: &LogRecord<'_>) -> io::Result<usize> {
+        self.append_with_endianness(record.version, record.endianness, record.checksum, record.bytes)
+    }
+
+    fn append_with_endianness(
+        &mut self,
+        version: u16,
+        endianness: Endianness,
+        checksum: Option<u32>,
+        payload: &[u8],
+    ) -> io::Result<usize> {
+        let len = u32::try_from(payload.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "log record payload is too large"))?;
+
+        let header = RecordHeader {
+            version,
+            len,
+            endianness,
+            checksum,
+        };
+        header.write_to(&mut self.inner)?;
+        self.inner.write_all(payload)?;
+
+        let padding = header.padding();
+        self.inner.write_all(&[0u8; LOG_ALIGNMENT][..padding])?;
+
+        let payload_pos = self.pos + LOG_HEADER_SIZE;
+        self.pos = payload_pos + payload.len() + padding;
+        Ok(payload_pos)
+    }
+
+    /// Flushes the underlying sink.
+    #[inline]
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    /// Returns the underlying sink.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// A record read from a log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LogRecord<'a> {
+    /// The version of the record.
+    pub version: u16,
+    /// The byte order of the producer that wrote the payload.
+    ///
+    /// Payloads with a different byte order than [`Endianness::archived`] must be accessed with
+    /// [`checked_root`](Self::checked_root), and their primitives read with [`Endianness::read`].
+    pub endianness: Endianness,
+    /// The checksum of the payload, if the record has one. It has already been verified.
+    pub checksum: Option<u32>,
+    /// The position of the payload in the log.
+    pub pos: usize,
+    /// The payload of the record, aligned to [`LOG_ALIGNMENT`].
+    pub bytes: &'a [u8],
+}
+
+impl LogRecord<'_> {
+    /// Returns the header that frames the record.
+    #[inline]
+    pub fn header(&self) -> RecordHeader {
+        RecordHeader {
+            version: self.version,
+            len: self.bytes.len() as u32,
+            endianness: self.endianness,
+            checksum: self.checksum,
+        }
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<'a> LogRecord<'a> {
+    /// Returns the archived parts at the root of the payload after checking their metadata.
+    ///
+    /// The root pointer and its metadata are read in the byte order of the record, so records from
+    /// producers with either byte order can be accessed. Primitive fields of the archived parts are
+    /// not swapped, and must be read with [`Endianness::read`].
+    ///
+    /// # Safety
+    ///
+    /// The payload must contain an archive with a `T` as its unsized root that was written in the
+    /// byte order of the record. If that is not the byte order of the current build, the archived
+    /// parts must only have primitive fields, since relative pointers in them are not swapped.
+    pub unsafe fn checked_root<T>(&self) -> Result<&'a T::Archived, MetadataError>
+    where
+        T: ArchiveUnsized + ?Sized,
+        T::Archived: CheckMetadata,
+    {
+        checked_unsized_root_in_order::<T>(self.bytes, self.endianness != Endianness::archived())
+    }
+}
+
+/// An error that occurred while reading a log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogError {
+    /// The log was not aligned to [`LOG_ALIGNMENT`].
+    Misaligned,
+    /// The log ended partway through a record header.
+    TruncatedHeader {
+        /// The position of the header.
+        pos: usize,
+    },
+    /// The log ended partway through a record payload.
+    TruncatedPayload {
+        /// The position of the payload.
+        pos: usize,
+        /// The length of the payload according to its header.
+        len: usize,
+    },
+    /// A record header had a format version that is not supported.
+    UnsupportedFormat {
+        /// The position of the header.
+        pos: usize,
+        /// The format version of the header.
+        format_version: u8,
+    },
+    /// A record header had unknown flags or nonzero reserved bytes.
+    InvalidHeader {
+        /// The position of the header.
+        pos: usize,
+    },
+    /// A record payload was longer than the maximum length set on the reader.
+    PayloadTooLarge {
+        /// The position of the payload.
+        pos: usize,
+        /// The length of the payload according to its header.
+        len: usize,
+        /// The maximum payload length.
+        max_len: usize,
+    },
+    /// A record payload did not match the checksum in its header.
+    ChecksumMismatch {
+        /// The position of the payload.
+        pos: usize,
+    },
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Misaligned => write!(f, "log is not aligned to {} bytes", LOG_ALIGNMENT),
+            Self::TruncatedHeader { pos } => write!(f, "log ended partway through the record header at {}", pos),
+            Self::TruncatedPayload { pos, len } => write!(
+                f,
+                "log ended partway through the {}-byte record payload at {}",
+                len,
+                pos,
+            ),
+            Self::UnsupportedFormat { pos, format_version } => write!(
+                f,
+                "record header at {} has unsupported format version {}",
+                pos,
+                format_version,
+            ),
+            Self::InvalidHeader { pos } => write!(f, "record header at {} has unknown flags or nonzero reserved bytes", pos),
+            Self::PayloadTooLarge { pos, len, max_len } => write!(
+                f,
+                "record payload at {} is {} bytes long, which is more than the maximum of {}",
+                pos,
+                len,
+                max_len,
+            ),
+            Self::ChecksumMismatch { pos } => write!(f, "record payload at {} does not match its checksum", pos),
+        }
+    }
+}
+
+impl Error for LogError {}
+
+/// Iterates over the records of a log written by a [`LogWriter`] without copying them.
+///
+/// Iteration stops after the first error.
+pub struct LogReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    max_len: usize,
+    failed: bool,
+}
+
+impl<'a> LogReader<'a> {
+    /// Creates a new reader over the bytes of a log.
+    ///
+    /// The bytes must be aligned to [`LOG_ALIGNMENT`] for the record payloads to be aligned.
+    /// Misaligned bytes are reported as an error on the first call to `next`.
+    #[inline]
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            pos: 0,
+            max_len: usize::MAX,
+            failed: false,
+        }
+    }
+
+    /// Sets the maximum length of a record payload.
+    ///
+    /// Longer payloads are reported as an error before they are read, which bounds the amount of
+    /// data passed on from an untrusted log.
+    #[inline]
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    fn read_record(&mut self) -> Result<LogRecord<'a>, LogError> {
+        if self.pos == 0 && self.bytes.as_ptr() as usize & (LOG_ALIGNMENT - 1) != 0 {
+            return Err(LogError::Misaligned);
+        }
+
+        let header_pos = self.pos;
+        let header_bytes = self.bytes.get(header_pos..header_pos + LOG_HEADER_SIZE)
+            .ok_or(LogError::TruncatedHeader { pos: header_pos })?;
+        // The slice is exactly LOG_HEADER_SIZE bytes long
+        let header_bytes = <&[u8; LOG_HEADER_SIZE]>::try_from(header_bytes).unwrap();
+        let format_version = RecordHeader::format_version(header_bytes);
+        if format_version != LOG_FORMAT_VERSION {
+            return Err(LogError::UnsupportedFormat { pos: header_pos, format_version });
+        }
+        let header = RecordHeader::parse(header_bytes)
+            .ok_or(LogError::InvalidHeader { pos: header_pos })?;
+        let len = header.len as usize;
+
+        let pos = header_pos + LOG_HEADER_SIZE;
+        if len > self.max_len {
+            return Err(LogError::PayloadTooLarge { pos, len, max_len: self.max_len });
+        }
+        // A hostile length can overflow the end of the payload on 32-bit targets
+        let bytes = pos.checked_add(len)
+            .and_then(|end| self.bytes.get(pos..end))
+            .ok_or(LogError::TruncatedPayload { pos, len })?;
+        if !header.matches(bytes) {
+            return Err(LogError::ChecksumMismatch { pos });
+        }
+
+        self.pos = (pos + len).saturating_add(header.padding()).min(self.bytes.len());
+
+        Ok(LogRecord {
+            version: header.version,
+            endianness: header.endianness,
+            checksum: header.checksum,
+            pos,
+            bytes,
+        })
+    }
+}
+
+impl<'a> Iterator for LogReader<'a> {
+    type Item = Result<LogRecord<'a>, LogError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.pos == self.bytes.len() {
+            return None;
+        }
+
+        let result = self.read_record();
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+/// A single record that owns its header and payload in one aligned allocation.
+///
+/// The bytes of an owned record are a log with just that record in it, so owned records can be
+/// kept in slabs or ring buffers and appended to other logs as-is.
+#[cfg(feature = "rkyv")]
+#[derive(Clone, Debug)]
+pub struct OwnedRecord {
+    bytes: AlignedVec,
+}
+
+#[cfg(feature = "rkyv")]
+impl OwnedRecord {
+    /// Creates an owned record from a header and its payload.
+    ///
+    /// Returns `None` if the length in the header is not the length of the payload, or if the
+    /// payload does not match the checksum in the header.
+    pub fn from_parts(header: RecordHeader, payload: &[u8]) -> Option<Self> {
+        if !header.matches(payload) {
+            return None;
+        }
+
+        let padding = header.padding();
+        let mut bytes = AlignedVec::with_capacity(LOG_HEADER_SIZE + payload.len() + padding);
+        bytes.extend_from_slice(&header.to_bytes());
+        bytes.extend_from_slice(payload);
+        bytes.extend_from_slice(&[0u8; LOG_ALIGNMENT][..padding]);
+        Some(Self { bytes })
+    }
+
+    /// Creates an owned record from the bytes of a framed record, such as the bytes returned by
+    /// [`as_bytes`](Self::as_bytes).
+    ///
+    /// The bytes do not need to be aligned, and any bytes after the record are ignored.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LogError> {
+        let header_bytes = bytes.get(..LOG_HEADER_SIZE)
+            .ok_or(LogError::TruncatedHeader { pos: 0 })?;
+        // The slice is exactly LOG_HEADER_SIZE bytes long
+        let header_bytes = <&[u8; LOG_HEADER_SIZE]>::try_from(header_bytes).unwrap();
+        let format_version = RecordHeader::format_version(header_bytes);
+        if format_version != LOG_FORMAT_VERSION {
+            return Err(LogError::UnsupportedFormat { pos: 0, format_version });
+        }
+        let header = RecordHeader::parse(header_bytes)
+            .ok_or(LogError::InvalidHeader { pos: 0 })?;
+
+        let len = header.len as usize;
+        let payload = LOG_HEADER_SIZE.checked_add(len)
+            .and_then(|end| bytes.get(LOG_HEADER_SIZE..end))
+            .ok_or(LogError::TruncatedPayload { pos: LOG_HEADER_SIZE, len })?;
+        Self::from_parts(header, payload).ok_or(LogError::ChecksumMismatch { pos: LOG_HEADER_SIZE })
+    }
+
+    /// Returns the header of the record.
+    #[inline]
+    pub fn header(&self) -> RecordHeader {
+        let header_bytes = <&[u8; LOG_HEADER_SIZE]>::try_from(&self.bytes[..LOG_HEADER_SIZE]).unwrap();
+        // The header was validated when the record was created
+        RecordHeader::parse(header_bytes).unwrap()
+    }
+
+    /// Returns the payload of the record, aligned to [`LOG_ALIGNMENT`].
+    #[inline]
+    pub fn payload(&self) -> &[u8] {
+        let len = self.header().len as usize;
+        &self.bytes[LOG_HEADER_SIZE..LOG_HEADER_SIZE + len]
+    }
+
+    /// Returns the record as if it were read from a log that contains only this record.
+    #[inline]
+    pub fn as_record(&self) -> LogRecord<'_> {
+        let header = self.header();
+        LogRecord {
+            version: header.version,
+            endianness: header.endianness,
+            checksum: header.checksum,
+            pos: LOG_HEADER_SIZE,
+            bytes: self.payload(),
+        }
+    }
+
+    /// Returns the framed bytes of the record, including the padding after the payload.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes.as_slice()
+    }
+
+    /// Returns the aligned buffer that holds the record.
+    #[inline]
+    pub fn into_inner(self) -> AlignedVec {
+        self.bytes
+    }
+}
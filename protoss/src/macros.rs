@@ -0,0 +1,463 @@
+/// Fails to compile if a `#[repr(C)]` type has trailing padding after its last field.
+///
+/// Versioned types are accessed by truncating their bytes to the size of a version, so trailing
+/// padding in a version would be exposed as part of the next version's bytes. Manual implementors
+/// of [`Versioned`](crate::Versioned) can use this to check each version struct at compile time.
+///
+/// # Examples
+///
+/// ```
+/// #[repr(C)]
+/// struct ExampleV0 {
+///     a: u32,
+///     b: u32,
+/// }
+///
+/// protoss::assert_no_trailing_padding!(ExampleV0, b: u32);
+/// ```
+///
+/// A struct with trailing padding fails to compile:
+///
+/// ```compile_fail
+/// #[repr(C)]
+/// struct ExampleV0 {
+///     a: u32,
+///     b: u8,
+/// }
+///
+/// protoss::assert_no_trailing_padding!(ExampleV0, b: u8);
+/// ```
+///
+/// So does naming a field with the wrong type:
+///
+/// ```compile_fail
+/// #[repr(C)]
+/// struct ExampleV0 {
+///     a: u32,
+///     b: u32,
+/// }
+///
+/// protoss::assert_no_trailing_padding!(ExampleV0, b: u64);
+/// ```
+///
+/// Or a field that is not the last field:
+///
+/// ```compile_fail
+/// #[repr(C)]
+/// struct ExampleV0 {
+///     a: u32,
+///     b: u32,
+/// }
+///
+/// protoss::assert_no_trailing_padding!(ExampleV0, a: u32);
+/// ```
+#[macro_export]
+macro_rules! assert_no_trailing_padding {
+    ($ty:ty, $field:ident: $field_ty:ty) => {
+        const _: () = {
+            // Checks that the field has the given type
+            let _: fn(&$ty) -> &$field_ty = |value| &value.$field;
+            let end = ::core::mem::offset_of!($ty, $field) + ::core::mem::size_of::<$field_ty>();
+            if end != ::core::mem::size_of::<$ty>() {
+                panic!(concat!(
+                    "`",
+                    stringify!($ty),
+                    "` has trailing padding after `",
+                    stringify!($field),
+                    "`",
+                ));
+            }
+        };
+    };
+}
+
+/// Implements [`Versioned`](crate::Versioned) for a type from a list of its version structs.
+///
+/// Each version struct must be a `#[repr(C)]` prefix of the versioned type containing the fields
+/// of that version and all previous versions. The versions are numbered from zero in the order
+/// they are listed, and the last version is the latest. The accessor must be a `#[repr(C)]` type
+/// ending in a `[u8]` whose metadata is its length in bytes.
+///
+/// The sizes of the versions are checked at compile time: each version must be strictly larger
+/// than the previous one, and the latest version must be the same size as the versioned type. Use
+/// [`assert_no_trailing_padding!`] on each version struct to also check that none of them end in
+/// padding.
+///
+/// # Safety
+///
+/// The invocation must be prefixed with `unsafe` to acknowledge the requirements of
+/// [`Versioned`](crate::Versioned): each version struct must have the same layout as the
+/// beginning of the versioned type, and the accessor must be at least as aligned as the versioned
+/// type.
+///
+/// # Examples
+///
+/// ```
+/// use protoss::Proto;
+///
+/// #[repr(C)]
+/// struct ExampleV0 {
+///     a: u32,
+/// }
+///
+/// #[repr(C)]
+/// struct Example {
+///     a: u32,
+///     b: u32,
+/// }
+///
+/// #[repr(C)]
+/// #[derive(ptr_meta::Pointee)]
+/// struct ExampleAccessor {
+///     _align: [Example; 0],
+///     bytes: [u8],
+/// }
+///
+/// protoss::register_versions!(unsafe Example as ExampleAccessor => [ExampleV0, Example]);
+///
+/// let proto = Proto::latest(Example { a: 1, b: 2 });
+/// assert!(proto.is_latest());
+/// assert_eq!(proto.into_boxed_accessor().bytes.len(), 8);
+/// ```
+///
+/// Versions that do not grow fail to compile:
+///
+/// ```compile_fail
+/// #[repr(C)]
+/// struct ExampleV0 {
+///     a: u32,
+/// }
+///
+/// #[repr(C)]
+/// struct Example {
+///     a: u32,
+/// }
+///
+/// #[repr(C)]
+/// #[derive(ptr_meta::Pointee)]
+/// struct ExampleAccessor {
+///     _align: [Example; 0],
+///     bytes: [u8],
+/// }
+///
+/// protoss::register_versions!(unsafe Example as ExampleAccessor => [ExampleV0, Example]);
+/// ```
+///
+/// So does a latest version that is smaller than the versioned type:
+///
+/// ```compile_fail
+/// #[repr(C)]
+/// struct ExampleV0 {
+///     a: u32,
+/// }
+///
+/// #[repr(C)]
+/// struct Example {
+///     a: u32,
+///     b: u32,
+/// }
+///
+/// #[repr(C)]
+/// #[derive(ptr_meta::Pointee)]
+/// struct ExampleAccessor {
+///     _align: [Example; 0],
+///     bytes: [u8],
+/// }
+///
+/// protoss::register_versions!(unsafe Example as ExampleAccessor => [ExampleV0]);
+/// ```
+#[macro_export]
+macro_rules! register_versions {
+    (unsafe $ty:ty as $accessor:ty => [$($version:ty),+ $(,)?]) => {
+        const _: () = {
+            const SIZES: &[usize] = &[$(::core::mem::size_of::<$version>()),+];
+            let mut i = 1;
+            while i < SIZES.len() {
+                if SIZES[i] <= SIZES[i - 1] {
+                    panic!("each version must be larger than the previous version");
+                }
+                i += 1;
+            }
+            if SIZES[SIZES.len() - 1] != ::core::mem::size_of::<$ty>() {
+                panic!("the latest version must be the same size as the versioned type");
+            }
+        };
+
+        unsafe impl $crate::Versioned for $ty {
+            type Accessor = $accessor;
+            type Version = u16;
+
+            const LATEST: u16 = {
+                const SIZES: &[usize] = &[$(::core::mem::size_of::<$version>()),+];
+                (SIZES.len() - 1) as u16
+            };
+
+            #[inline]
+            fn accessor_metadata(version: u16) -> usize {
+                const SIZES: &[usize] = &[$(::core::mem::size_of::<$version>()),+];
+                SIZES[version as usize]
+            }
+        }
+    };
+}
+
+/// Defines an accessor for a versioned type with accessor methods for each of its fields.
+///
+/// The accessor is a `#[repr(C)]` struct ending in a `[u8]`, and a field is present if it lies
+/// entirely within those bytes. Each field is listed with the names of its shared and mutable
+/// accessor methods and its type. Present fields are dropped when the accessor is dropped.
+///
+/// This pairs with [`register_versions!`] to implement [`Versioned`](crate::Versioned) without
+/// the derive.
+///
+/// # Examples
+///
+/// ```
+/// use protoss::Proto;
+///
+/// #[repr(C)]
+/// struct ExampleV0 {
+///     a: u32,
+/// }
+///
+/// #[repr(C)]
+/// struct Example {
+///     a: u32,
+///     b: String,
+/// }
+///
+/// protoss::impl_accessor! {
+///     struct ExampleAccessor for Example {
+///         a (a_mut): u32,
+///         b (b_mut): String,
+///     }
+/// }
+///
+/// protoss::register_versions!(unsafe Example as ExampleAccessor => [ExampleV0, Example]);
+///
+/// let mut accessor = Proto::latest(Example { a: 1, b: String::from("foo") }).into_boxed_accessor();
+/// assert_eq!(accessor.a(), Some(&1));
+/// assert_eq!(accessor.b(), Some(&String::from("foo")));
+///
+/// *accessor.a_mut().unwrap() = 2;
+/// assert_eq!(accessor.a(), Some(&2));
+/// ```
+#[macro_export]
+macro_rules! impl_accessor {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $accessor:ident for $ty:ty {
+            $($field_vis:vis $field:ident ($field_mut:ident): $field_ty:ty,)*
+        }
+    ) => {
+        $(#[$attr])*
+        #[repr(C)]
+        $vis struct $accessor {
+            _align: [$ty; 0],
+            bytes: [u8],
+        }
+
+        impl $crate::__private::ptr_meta::Pointee for $accessor {
+            type Metadata = usize;
+        }
+
+        impl Drop for $accessor {
+            fn drop(&mut self) {
+                unsafe {
+                    $(
+                        if let Some(field) = self.$field_mut() {
+                            ::core::ptr::drop_in_place(field as *mut $field_ty);
+                        }
+                    )*
+                }
+            }
+        }
+
+        #[allow(dead_code)]
+        impl $accessor {
+            $(
+                #[inline]
+                $field_vis fn $field(&self) -> Option<&$field_ty> {
+                    let offset = ::core::mem::offset_of!($ty, $field);
+                    let size = ::core::mem::size_of::<$field_ty>();
+                    if offset + size > self.bytes.len() {
+                        None
+                    } else {
+                        unsafe {
+                            let struct_ptr = (self as *const Self).cast::<$ty>();
+                            Some(&*::core::ptr::addr_of!((*struct_ptr).$field))
+                        }
+                    }
+                }
+
+                #[inline]
+                $field_vis fn $field_mut(&mut self) -> Option<&mut $field_ty> {
+                    let offset = ::core::mem::offset_of!($ty, $field);
+                    let size = ::core::mem::size_of::<$field_ty>();
+                    if offset + size > self.bytes.len() {
+                        None
+                    } else {
+                        unsafe {
+                            let struct_ptr = (self as *mut Self).cast::<$ty>();
+                            Some(&mut *::core::ptr::addr_of_mut!((*struct_ptr).$field))
+                        }
+                    }
+                }
+            )*
+        }
+    };
+}
+
+/// Records the evolution history of versioned types in one place, and fails to compile if a type
+/// no longer matches its recorded history.
+///
+/// Each type lists its versions in order, along with the names of the fields added in each
+/// version. Adding a field or version to a type without recording it here is a compile error, so
+/// the history doubles as a reviewable changelog of the wire format.
+///
+/// # Examples
+///
+/// ```
+/// #[protoss::protoss]
+/// pub struct Example {
+///     #[version = 0]
+///     pub a: u32,
+///     pub b: u32,
+///     #[version = 1]
+///     pub c: u32,
+/// }
+///
+/// protoss::evolution_history! {
+///     Example {
+///         0 => [a, b],
+///         1 => [c],
+///     }
+/// }
+/// ```
+///
+/// Adding a field without recording it fails to compile:
+///
+/// ```compile_fail
+/// #[protoss::protoss]
+/// pub struct Example {
+///     #[version = 0]
+///     pub a: u32,
+///     pub b: u32,
+///     #[version = 1]
+///     pub c: u32,
+///     pub d: u32,
+/// }
+///
+/// protoss::evolution_history! {
+///     Example {
+///         0 => [a, b],
+///         1 => [c],
+///     }
+/// }
+/// ```
+///
+/// So does moving a field to a different version:
+///
+/// ```compile_fail
+/// #[protoss::protoss]
+/// pub struct Example {
+///     #[version = 0]
+///     pub a: u32,
+///     pub b: u32,
+///     #[version = 1]
+///     pub c: u32,
+/// }
+///
+/// protoss::evolution_history! {
+///     Example {
+///         0 => [a],
+///         1 => [b, c],
+///     }
+/// }
+/// ```
+///
+/// Or adding a version without recording it:
+///
+/// ```compile_fail
+/// #[protoss::protoss]
+/// pub struct Example {
+///     #[version = 0]
+///     pub a: u32,
+///     pub b: u32,
+///     #[version = 1]
+///     pub c: u32,
+/// }
+///
+/// protoss::evolution_history! {
+///     Example {
+///         0 => [a, b, c],
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! evolution_history {
+    ($($ty:ident { $($version:literal => [$($field:ident),* $(,)?]),+ $(,)? })*) => {
+        $(
+            const _: () = {
+                assert!(
+                    $crate::__private::versions_eq($ty::evolution_history(), &[$($version),+]),
+                    concat!("the versions of `", stringify!($ty), "` do not match its evolution history"),
+                );
+                assert!(
+                    $crate::__private::fields_added_eq(
+                        $ty::evolution_history(),
+                        &[$(&[$(stringify!($field)),*]),+],
+                    ),
+                    concat!("the fields of `", stringify!($ty), "` do not match its evolution history"),
+                );
+            };
+        )*
+    };
+}
+
+/// Branches on the version of a [`Proto`](crate::Proto), binding its accessor if the version
+/// matches.
+///
+/// `>= version` matches data that is at least the given version, which has all of the fields of
+/// that version. `== version` only matches data that is exactly the given version. The `else`
+/// branch is optional.
+///
+/// # Examples
+///
+/// ```
+/// use protoss::{if_version, protoss};
+///
+/// #[protoss]
+/// pub struct Example {
+///     #[version = 0]
+///     pub a: u32,
+///     #[version = 1]
+///     pub b: u32,
+/// }
+///
+/// fn sum(example: &protoss::Proto<Example>) -> u32 {
+///     if_version!(example, >= 1 => |parts| {
+///         parts.a().unwrap() + parts.b().unwrap()
+///     } else {
+///         *example.access().a().unwrap()
+///     })
+/// }
+///
+/// assert_eq!(sum(&Example::partial_v0(1)), 1);
+/// assert_eq!(sum(&Example::partial_v1(1, 2)), 3);
+/// ```
+#[macro_export]
+macro_rules! if_version {
+    ($proto:expr, >= $version:expr => |$parts:pat| $then:block $(else $else:block)?) => {
+        match $crate::Proto::access_at_least(&$proto, $version) {
+            Some($parts) => $then,
+            None => { $($else)? }
+        }
+    };
+    ($proto:expr, == $version:expr => |$parts:pat| $then:block $(else $else:block)?) => {
+        match $crate::Proto::access_exact(&$proto, $version) {
+            Some($parts) => $then,
+            None => { $($else)? }
+        }
+    };
+}
@@ -3,7 +3,9 @@ use core::{
     mem::{self, MaybeUninit},
     ptr,
 };
-use crate::Versioned;
+#[cfg(not(feature = "std"))]
+use ::alloc::boxed::Box;
+use crate::{copy_to_box, SmallAccessor, Versioned};
 
 /// Some version of a versioned type.
 pub struct Proto<T: Versioned> {
@@ -47,8 +49,26 @@ impl<T: Versioned> Proto<T> {
         }
     }
 
+    /// Decomposes the proto into its partially-initialized value and its version.
+    ///
+    /// The fields specified by the version are initialized and must be dropped by the caller.
+    #[inline]
+    pub fn into_raw_parts(self) -> (MaybeUninit<T>, T::Version) {
+        let this = mem::ManuallyDrop::new(self);
+        // SAFETY: this will not be dropped, so the value is moved out exactly once
+        let value = unsafe { ptr::read(&this.value) };
+        (value, this.version)
+    }
+
+    /// Returns the version of the data.
     #[inline]
-    fn access(&self) -> &T::Accessor {
+    pub fn version(&self) -> T::Version {
+        self.version
+    }
+
+    /// Returns an accessor for the data.
+    #[inline]
+    pub fn access(&self) -> &T::Accessor {
         unsafe {
             // SAFETY:
             // - self.value.as_ptr() is a valid pointer to T::Accesor
@@ -60,8 +80,9 @@ impl<T: Versioned> Proto<T> {
         }
     }
 
+    /// Returns a mutable accessor for the data.
     #[inline]
-    fn access_mut(&mut self) -> &mut T::Accessor {
+    pub fn access_mut(&mut self) -> &mut T::Accessor {
         unsafe {
             // SAFETY:
             // - self.value.as_ptr() is a valid pointer to T::Accesor
@@ -73,11 +94,101 @@ impl<T: Versioned> Proto<T> {
         }
     }
 
+    /// Returns whether the data is at least the given version.
+    ///
+    /// Newer versions only add fields, so data that is at least some version has all of the
+    /// fields of that version.
+    #[inline]
+    pub fn version_at_least(&self, version: T::Version) -> bool
+    where
+        T::Version: PartialOrd,
+    {
+        self.version >= version
+    }
+
+    /// Returns an accessor for the data if it is at least the given version.
+    #[inline]
+    pub fn access_at_least(&self, version: T::Version) -> Option<&T::Accessor>
+    where
+        T::Version: PartialOrd,
+    {
+        if self.version_at_least(version) {
+            Some(self.access())
+        } else {
+            None
+        }
+    }
+
+    /// Returns an accessor for the data only if it is exactly the given version.
+    ///
+    /// This is for consumers that must not silently accept newer data.
+    #[inline]
+    pub fn access_exact(&self, version: T::Version) -> Option<&T::Accessor> {
+        if self.version == version {
+            Some(self.access())
+        } else {
+            None
+        }
+    }
+
     /// Returns whether the data is the latest version.
+    #[inline]
     pub fn is_latest(&self) -> bool {
         self.version == T::LATEST
     }
 
+    /// Returns whether the data is older than the latest version, so it could be upgraded.
+    #[inline]
+    pub fn is_stale(&self) -> bool {
+        !self.is_latest()
+    }
+
+    /// Returns a reference to the versioned type if the data is the latest version.
+    #[inline]
+    pub fn as_latest(&self) -> Option<&T> {
+        if self.is_latest() {
+            unsafe {
+                Some(self.as_latest_unchecked())
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the versioned type if the data is the latest version.
+    #[inline]
+    pub fn as_latest_mut(&mut self) -> Option<&mut T> {
+        if self.is_latest() {
+            unsafe {
+                Some(self.as_latest_mut_unchecked())
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Returns a reference to the versioned type without checking whether the data is the latest
+    /// version.
+    ///
+    /// # Safety
+    ///
+    /// The data must be the latest version.
+    #[inline]
+    pub unsafe fn as_latest_unchecked(&self) -> &T {
+        &*self.value.as_ptr()
+    }
+
+    /// Returns a mutable reference to the versioned type without checking whether the data is the
+    /// latest version.
+    ///
+    /// # Safety
+    ///
+    /// The data must be the latest version.
+    #[inline]
+    pub unsafe fn as_latest_mut_unchecked(&mut self) -> &mut T {
+        &mut *self.value.as_mut_ptr()
+    }
+
     /// Unwraps the versioned type if the data is the latest version.
     ///
     /// If the data is not the latest version, `Err` is returned with the original value.
@@ -102,41 +213,24 @@ impl<T: Versioned> Proto<T> {
         self.try_unwrap().expect("attempted to unwrap a Version that was not the latest version")
     }
 
+    /// Converts the versioned type into an accessor that is stored inline if it fits in `N`
+    /// bytes.
+    #[inline]
+    pub fn into_small_accessor<const N: usize>(self) -> SmallAccessor<T, N> {
+        SmallAccessor::new(self)
+    }
+
     /// Converts the versioned type into a boxed accessor.
     pub fn into_boxed_accessor(mut self) -> Box<T::Accessor> {
-        unsafe {
-            #[cfg(feature = "std")]
-            use ::std::alloc::alloc;
-            #[cfg(not(feature = "std"))]
-            use ::alloc::alloc::alloc;
-
-            use ::core::{alloc::Layout, mem::{size_of_val, align_of_val, forget}};
-
-            let accessor = self.access_mut();
-            // SAFETY: align is a non-zero power of two which does not exceed usize::MAX when
-            // rounded up to the nearest multiple of align
-            let layout = Layout::from_size_align_unchecked(size_of_val(accessor), align_of_val(accessor));
-            let ptr = if layout.size() == 0 {
-                // SAFETY: layout.align() is non-zero
-                ptr::NonNull::new_unchecked(layout.align() as *mut u8).as_ptr()
-            } else {
-                // SAFETY: layout has non-zero size
-                let ptr = alloc(layout);
-                // SAFETY:
-                // - accessor is valid for reads
-                // - ptr is valid for writes
-                ptr::copy_nonoverlapping(
-                    accessor as *const _ as *const u8,
-                    ptr.cast::<u8>(),
-                    layout.size(),
-                );
-                ptr
-            };
-            let accessor_ptr = ::ptr_meta::from_raw_parts_mut(ptr.cast(), T::accessor_metadata(self.version));
-            forget(self);
-            // SAFETY: accessor_ptr conforms to the memory layout required by Box
-            Box::from_raw(accessor_ptr)
-        }
+        let accessor = self.access_mut();
+        debug_assert!(
+            mem::align_of_val(accessor) >= mem::align_of::<T>(),
+            "accessors must be at least as aligned as their versioned type",
+        );
+        // SAFETY: self is forgotten below, so the accessor is only dropped through the box
+        let result = unsafe { copy_to_box(accessor) };
+        mem::forget(self);
+        result
     }
 }
 
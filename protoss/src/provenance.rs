@@ -0,0 +1,59 @@
+/// Records which fields of a converted value were read from the original data and which were
+/// filled in with defaults.
+///
+/// Fields are numbered in [wire order](crate::DescribeFields#wire-order). Because each version only
+/// adds fields to the previous one, the fields that were read are always a prefix of all the fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldProvenance {
+    read: usize,
+    len: usize,
+}
+
+impl FieldProvenance {
+    /// Creates a new field provenance where the first `read` of `len` fields were read.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `read` is greater than `len`.
+    #[inline]
+    pub fn new(read: usize, len: usize) -> Self {
+        assert!(read <= len, "cannot read more fields than there are");
+        Self { read, len }
+    }
+
+    /// Returns the total number of fields.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether there are no fields.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of fields that were read from the original data.
+    #[inline]
+    pub fn read_count(&self) -> usize {
+        self.read
+    }
+
+    /// Returns the number of fields that were filled in with defaults.
+    #[inline]
+    pub fn defaulted_count(&self) -> usize {
+        self.len - self.read
+    }
+
+    /// Returns whether the field at `index` was read from the original data.
+    #[inline]
+    pub fn is_read(&self, index: usize) -> bool {
+        index < self.read
+    }
+
+    /// Returns whether the field at `index` was filled in with a default.
+    #[inline]
+    pub fn is_defaulted(&self, index: usize) -> bool {
+        index >= self.read && index < self.len
+    }
+}
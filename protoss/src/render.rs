@@ -0,0 +1,58 @@
+use core::fmt::{self, Write};
+use std::string::String;
+
+/// A single field of a versioned type, as displayed by [`render_fields`].
+pub struct RenderField<'a> {
+    /// The name of the field.
+    pub name: &'static str,
+    /// The version the field was added in.
+    pub version: u16,
+    /// The value of the field, or `None` if the field is absent.
+    pub value: Option<&'a dyn fmt::Debug>,
+}
+
+/// Renders an aligned table of fields with the version they were added in, whether they are
+/// present, and their `Debug` output.
+pub fn render_fields(fields: &[RenderField<'_>]) -> String {
+    const HEADER: [&str; 4] = ["field", "since", "present", "value"];
+
+    let rows = fields.iter()
+        .map(|field| [
+            String::from(field.name),
+            field.version.to_string(),
+            String::from(if field.value.is_some() { "yes" } else { "no" }),
+            field.value.map(|value| format!("{:?}", value)).unwrap_or_default(),
+        ])
+        .collect::<Vec<_>>();
+
+    let mut widths = HEADER.map(str::len);
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut result = String::new();
+    let mut write_row = |cells: [&str; 4]| {
+        let [name, version, present, value] = cells;
+        let line = format!(
+            "{:<name_width$}  {:>version_width$}  {:<present_width$}  {}",
+            name,
+            version,
+            present,
+            value,
+            name_width = widths[0],
+            version_width = widths[1],
+            present_width = widths[2],
+        );
+        // Writing to a String is infallible
+        let _ = writeln!(result, "{}", line.trim_end());
+    };
+
+    write_row(HEADER);
+    for row in rows.iter() {
+        write_row([&row[0], &row[1], &row[2], &row[3]]);
+    }
+
+    result
+}
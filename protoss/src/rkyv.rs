@@ -1,3 +1,500 @@
 // TODO: wrapper types for Proto<T>
 // - Serialize a Proto<T> as a Box<T::Accessor>
 // - Serialize an Rc/Arc<Proto<T>> as an Rc/Arc<T::Accessor>
+
+use core::{fmt, marker::PhantomData, mem::{align_of, size_of, MaybeUninit}, ops::Deref, ptr, slice};
+#[cfg(not(feature = "std"))]
+use ::alloc::vec::Vec;
+use ::ptr_meta::Pointee;
+use ::rkyv::{archived_root, archived_unsized_root, AlignedVec, Archive, ArchivePointee, ArchiveUnsized, Archived, RelPtr};
+use crate::{VersionOf, Versioned};
+#[cfg(feature = "std")]
+use ::rkyv::{
+    ser::{
+        serializers::{AllocScratch, CompositeSerializer, FallbackScratch, HeapScratch, SharedSerializeMap, WriteSerializer},
+        Serializer,
+    },
+    Fallible,
+    SerializeUnsized,
+};
+#[cfg(feature = "std")]
+use ::std::io::Write;
+#[cfg(feature = "validation")]
+use ::rkyv::{
+    bytecheck::CheckBytes,
+    validation::{validators::DefaultValidator, ArchiveContext, LayoutRaw},
+};
+
+/// Bytes that are aligned for accessing archived data.
+///
+/// Archived data must be accessed from a properly aligned buffer, but bytes read from files and
+/// sockets often are not. `AlignedBytes` borrows the original bytes when they are already aligned
+/// and copies them into an [`AlignedVec`] otherwise.
+pub enum AlignedBytes<'a> {
+    /// The original bytes, which were already aligned.
+    Borrowed(&'a [u8]),
+    /// A copy of the original bytes in an aligned buffer.
+    Owned(AlignedVec),
+}
+
+impl<'a> AlignedBytes<'a> {
+    /// Returns the given bytes if they are aligned to `align`, or an aligned copy of them
+    /// otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two or is greater than [`AlignedVec::ALIGNMENT`].
+    pub fn new(bytes: &'a [u8], align: usize) -> Self {
+        assert!(align.is_power_of_two(), "alignment must be a power of two");
+        assert!(
+            align <= AlignedVec::ALIGNMENT,
+            "alignment must be at most AlignedVec::ALIGNMENT",
+        );
+
+        if bytes.as_ptr() as usize & (align - 1) == 0 {
+            Self::Borrowed(bytes)
+        } else {
+            let mut vec = AlignedVec::with_capacity(bytes.len());
+            vec.extend_from_slice(bytes);
+            Self::Owned(vec)
+        }
+    }
+
+    /// Returns the given bytes if they are aligned for a `T`, or an aligned copy of them
+    /// otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the alignment of `T` is greater than [`AlignedVec::ALIGNMENT`].
+    #[inline]
+    pub fn for_type<T>(bytes: &'a [u8]) -> Self {
+        Self::new(bytes, align_of::<T>())
+    }
+
+    /// Returns whether the original bytes were borrowed instead of copied.
+    #[inline]
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self, Self::Borrowed(_))
+    }
+}
+
+impl Deref for AlignedBytes<'_> {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Borrowed(bytes) => bytes,
+            Self::Owned(vec) => vec.as_slice(),
+        }
+    }
+}
+
+/// An archive that owns its bytes.
+///
+/// This is the owned counterpart to accessing archived data from a borrowed buffer, for bytes that
+/// were just read from a file or socket. The bytes are copied into an aligned buffer if needed,
+/// and the archive derefs to the archived root.
+///
+/// Bytes from an untrusted source can be checked with
+/// [`new_checked`](OwnedArchive::new_checked) when the `validation` feature is enabled.
+pub struct OwnedArchive<T: ArchiveUnsized + ?Sized> {
+    bytes: OwnedBytes,
+    _phantom: PhantomData<T>,
+}
+
+enum OwnedBytes {
+    Aligned(AlignedVec),
+    // A vector whose buffer happens to be aligned to AlignedVec::ALIGNMENT. It is never mutated,
+    // so the buffer stays where it is.
+    Vec(Vec<u8>),
+}
+
+impl<T: ArchiveUnsized + ?Sized> OwnedArchive<T> {
+    /// Creates an owned archive from an aligned buffer.
+    ///
+    /// # Safety
+    ///
+    /// The bytes must contain a valid archive with a `T` as its unsized root, such as one written
+    /// with `serialize_unsized_value`.
+    #[inline]
+    pub unsafe fn from_aligned_vec(bytes: AlignedVec) -> Self {
+        Self {
+            bytes: OwnedBytes::Aligned(bytes),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates an owned archive from the given bytes, copying them into an aligned buffer.
+    ///
+    /// # Safety
+    ///
+    /// The bytes must contain a valid archive with a `T` as its unsized root, such as one written
+    /// with `serialize_unsized_value`.
+    pub unsafe fn from_bytes(bytes: &[u8]) -> Self {
+        let mut vec = AlignedVec::with_capacity(bytes.len());
+        vec.extend_from_slice(bytes);
+        Self::from_aligned_vec(vec)
+    }
+
+    /// Creates an owned archive from a byte vector.
+    ///
+    /// The vector is kept as-is if its buffer is aligned to [`AlignedVec::ALIGNMENT`], and is
+    /// copied into an aligned buffer otherwise.
+    ///
+    /// # Safety
+    ///
+    /// The bytes must contain a valid archive with a `T` as its unsized root, such as one written
+    /// with `serialize_unsized_value`.
+    #[inline]
+    pub unsafe fn from_vec(bytes: Vec<u8>) -> Self {
+        if (bytes.as_ptr() as usize).is_multiple_of(AlignedVec::ALIGNMENT) {
+            Self {
+                bytes: OwnedBytes::Vec(bytes),
+                _phantom: PhantomData,
+            }
+        } else {
+            Self::from_bytes(&bytes)
+        }
+    }
+
+    /// Returns the bytes of the archive.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.bytes {
+            OwnedBytes::Aligned(bytes) => bytes.as_slice(),
+            OwnedBytes::Vec(bytes) => bytes.as_slice(),
+        }
+    }
+
+    /// Returns the aligned buffer that holds the archive.
+    ///
+    /// This copies the bytes if the archive was created from an aligned `Vec<u8>`.
+    #[inline]
+    pub fn into_inner(self) -> AlignedVec {
+        match self.bytes {
+            OwnedBytes::Aligned(bytes) => bytes,
+            OwnedBytes::Vec(bytes) => {
+                let mut vec = AlignedVec::with_capacity(bytes.len());
+                vec.extend_from_slice(&bytes);
+                vec
+            }
+        }
+    }
+}
+
+#[cfg(feature = "validation")]
+impl<T> OwnedArchive<T>
+where
+    T: ArchiveUnsized + ?Sized,
+    T::Archived: CheckMetadata + LayoutRaw + for<'a> CheckBytes<DefaultValidator<'a>>,
+{
+    /// Creates an owned archive from untrusted bytes, checking that they contain a valid archive
+    /// with a `T` as its unsized root.
+    ///
+    /// The metadata of the root is checked with [`checked_unsized_root`], and the archived data
+    /// is then validated with `bytecheck`. This requires the archived parts to implement
+    /// `CheckBytes`, which the `protoss` attribute does when given the `check_bytes` argument.
+    pub fn new_checked(bytes: Vec<u8>) -> Result<Self, OwnedArchiveError> {
+        // SAFETY: the archive is checked before it is returned, and is only read through raw
+        // pointers until then
+        let result = unsafe { Self::from_vec(bytes) };
+        result.check()?;
+        Ok(result)
+    }
+
+    fn check(&self) -> Result<(), OwnedArchiveError> {
+        fn invalid<E: std::error::Error + 'static>(error: E) -> OwnedArchiveError {
+            OwnedArchiveError::Invalid(Box::new(error))
+        }
+
+        let bytes = self.as_bytes();
+        unsafe {
+            // SAFETY: the root is checked to be in bounds and aligned, and archived parts are only
+            // bytes until they are checked
+            let root = checked_unsized_root::<T>(bytes)? as *const T::Archived;
+            let mut validator = DefaultValidator::new(bytes);
+            let range = validator.push_prefix_subtree(root).map_err(invalid)?;
+            <T::Archived as CheckBytes<DefaultValidator<'_>>>::check_bytes(root, &mut validator).map_err(invalid)?;
+            validator.pop_prefix_range(range).map_err(invalid)?;
+            validator.finish().map_err(invalid)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: ArchiveUnsized + ?Sized> Deref for OwnedArchive<T> {
+    type Target = T::Archived;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe {
+            // SAFETY: the bytes contain a valid archive with a T as its unsized root, and are
+            // aligned to AlignedVec::ALIGNMENT
+            archived_unsized_root::<T>(self.as_bytes())
+        }
+    }
+}
+
+/// An error that occurred while checking the bytes of an [`OwnedArchive`].
+#[cfg(feature = "validation")]
+#[derive(Debug)]
+pub enum OwnedArchiveError {
+    /// The metadata of the root could not be checked.
+    Metadata(MetadataError),
+    /// The archived data is not valid.
+    Invalid(Box<dyn std::error::Error>),
+}
+
+#[cfg(feature = "validation")]
+impl From<MetadataError> for OwnedArchiveError {
+    #[inline]
+    fn from(error: MetadataError) -> Self {
+        Self::Metadata(error)
+    }
+}
+
+#[cfg(feature = "validation")]
+impl fmt::Display for OwnedArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Metadata(error) => write!(f, "{}", error),
+            Self::Invalid(error) => write!(f, "archived data is not valid: {}", error),
+        }
+    }
+}
+
+#[cfg(feature = "validation")]
+impl std::error::Error for OwnedArchiveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Metadata(error) => Some(error),
+            Self::Invalid(error) => Some(error.as_ref()),
+        }
+    }
+}
+
+/// An error that occurred while checking the metadata of archived parts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetadataError {
+    /// The archived version is older than the latest known version, but is not one of the known
+    /// versions.
+    UnknownVersion {
+        /// The archived version.
+        version: u16,
+    },
+    /// The archived version is newer than the latest known version. This is only an error with
+    /// the `strict` feature enabled.
+    NewerVersion {
+        /// The archived version.
+        version: u16,
+    },
+    /// The archived size is not the size of any known version. This is only an error with the
+    /// `strict` feature enabled.
+    UnknownSize {
+        /// The archived size.
+        size: usize,
+    },
+    /// The archived parts do not lie within the bytes before the root pointer.
+    OutOfBounds,
+    /// The archived parts or the root pointer are not aligned.
+    Misaligned,
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownVersion { version } => write!(f, "unknown archived version {}", version),
+            Self::NewerVersion { version } => write!(f, "archived version {} is newer than the latest known version", version),
+            Self::UnknownSize { size } => write!(f, "archived size {} is not the size of a known version", size),
+            Self::OutOfBounds => write!(f, "archived parts are out of bounds"),
+            Self::Misaligned => write!(f, "archived parts are misaligned"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MetadataError {}
+
+/// Archived parts whose metadata can be checked before they are accessed.
+///
+/// This is implemented by the `protoss` attribute. Archived metadata that can not be checked
+/// produces archived parts with no fields when it is read without checking it.
+///
+/// # Safety
+///
+/// `ArchivedMetadata` must be a single archived integer, so that metadata written in the other byte
+/// order can be read by reversing its bytes.
+pub unsafe trait CheckMetadata: ArchivePointee + Pointee<Metadata = usize> {
+    /// The alignment of the archived parts.
+    const ALIGN: usize;
+
+    /// Returns the pointer metadata for the given archived metadata, or an error if it does not
+    /// describe a version that can be read.
+    fn check_metadata(archived: &Self::ArchivedMetadata) -> Result<usize, MetadataError>;
+}
+
+/// A version struct of a versioned type that is archived with rkyv.
+///
+/// This is implemented by the `protoss` attribute for each version struct when it is given the
+/// `rkyv` argument.
+pub trait ArchivedVersionOf: VersionOf {
+    /// The pointer metadata of archived parts of this version, which is their archived size.
+    ///
+    /// This is usually different from the accessor metadata returned by [`metadata_for`](crate::metadata_for),
+    /// since archived fields may have different sizes and alignments than their native types.
+    const ARCHIVED_METADATA: usize;
+}
+
+/// Returns the pointer metadata of archived parts of the version `V`.
+#[inline]
+pub fn archived_metadata_for<V: ArchivedVersionOf + ?Sized>() -> usize {
+    V::ARCHIVED_METADATA
+}
+
+/// Returns the archived parts at the root of the given bytes after checking their metadata and
+/// that they lie within the bytes before the root pointer and are aligned.
+///
+/// This is a fallible version of `archived_unsized_root` for archives with a version that may not
+/// be known to this build.
+///
+/// # Safety
+///
+/// The bytes must contain an archive with a `T` as its unsized root, such as one written with
+/// `serialize_unsized_value`. The archived metadata of the root does not need to be valid.
+pub unsafe fn checked_unsized_root<T>(bytes: &[u8]) -> Result<&T::Archived, MetadataError>
+where
+    T: ArchiveUnsized + ?Sized,
+    T::Archived: CheckMetadata,
+{
+    checked_unsized_root_in_order::<T>(bytes, false)
+}
+
+/// Checks the root of the given bytes like [`checked_unsized_root`], reversing the bytes of the
+/// root pointer and its metadata first if `swap` is set.
+pub(crate) unsafe fn checked_unsized_root_in_order<T>(bytes: &[u8], swap: bool) -> Result<&T::Archived, MetadataError>
+where
+    T: ArchiveUnsized + ?Sized,
+    T::Archived: CheckMetadata,
+{
+    let pos = bytes.len()
+        .checked_sub(size_of::<RelPtr<T::Archived>>())
+        .ok_or(MetadataError::OutOfBounds)?;
+    let rel_ptr_addr = bytes.as_ptr().add(pos);
+    if !(rel_ptr_addr as usize).is_multiple_of(align_of::<RelPtr<T::Archived>>()) {
+        return Err(MetadataError::Misaligned);
+    }
+    let rel_ptr = &*rel_ptr_addr.cast::<RelPtr<T::Archived>>();
+
+    let mut swapped_metadata = MaybeUninit::<<T::Archived as ArchivePointee>::ArchivedMetadata>::uninit();
+    let (offset, metadata) = if swap {
+        // Offsets are decoded to isize from an archived integer of the same size, so truncating
+        // them back to that size is lossless
+        let offset = match size_of::<Archived<isize>>() {
+            2 => (rel_ptr.offset() as i16).swap_bytes() as isize,
+            4 => (rel_ptr.offset() as i32).swap_bytes() as isize,
+            _ => (rel_ptr.offset() as i64).swap_bytes() as isize,
+        };
+        // SAFETY: the archived metadata is a single integer, so any bytes are valid for it and
+        // reversing them reads it in the other byte order
+        let len = size_of::<<T::Archived as ArchivePointee>::ArchivedMetadata>();
+        let metadata_bytes = swapped_metadata.as_mut_ptr().cast::<u8>();
+        ptr::copy_nonoverlapping((rel_ptr.metadata() as *const <T::Archived as ArchivePointee>::ArchivedMetadata).cast::<u8>(), metadata_bytes, len);
+        slice::from_raw_parts_mut(metadata_bytes, len).reverse();
+        (offset, &*swapped_metadata.as_ptr())
+    } else {
+        (rel_ptr.offset(), rel_ptr.metadata())
+    };
+
+    let size = T::Archived::check_metadata(metadata)?;
+    let start = (rel_ptr.base() as usize - bytes.as_ptr() as usize) as isize + offset;
+    // The root is always written before the pointer to it
+    if start < 0 || (start as usize).checked_add(size).is_none_or(|end| end > pos) {
+        return Err(MetadataError::OutOfBounds);
+    }
+    let root = bytes.as_ptr().add(start as usize);
+    if !(root as usize).is_multiple_of(T::Archived::ALIGN) {
+        return Err(MetadataError::Misaligned);
+    }
+    Ok(&*::ptr_meta::from_raw_parts(root.cast(), size))
+}
+
+/// Reads archives of a plain, unversioned type `L` as version 0 of a versioned type.
+///
+/// This is the migration path for types that were archived before they were versioned. If the
+/// fields of version 0 are the fields of `L`, in the same order and with the same types, and `L` is
+/// archived with `#[archive_attr(repr(C))]`, then an archived `L` has the same layout as archived
+/// parts of version 0 and existing archives can be read without migrating them.
+pub struct LegacyAsV0<L> {
+    _phantom: PhantomData<L>,
+}
+
+impl<L: Archive> LegacyAsV0<L> {
+    /// Returns the archived parts of version 0 of `T` that have the same bytes as the given
+    /// archived value.
+    ///
+    /// # Safety
+    ///
+    /// The fields of version 0 of `T` must be the fields of `L`, in the same order and with the
+    /// same types. `L` must be archived with `#[archive_attr(repr(C))]` so that `L::Archived` has
+    /// the same layout as archived version 0 of `T`, including its size and alignment. Without it,
+    /// rkyv may reorder the archived fields.
+    #[inline]
+    pub unsafe fn access<T>(archived: &L::Archived) -> &<T::Accessor as ArchiveUnsized>::Archived
+    where
+        T: Versioned + ?Sized,
+        T::Accessor: ArchiveUnsized,
+        <T::Accessor as ArchiveUnsized>::Archived: Pointee<Metadata = usize>,
+    {
+        &*::ptr_meta::from_raw_parts(
+            (archived as *const L::Archived).cast(),
+            core::mem::size_of::<L::Archived>(),
+        )
+    }
+
+    /// Returns the root of an archive of `L` as the archived parts of version 0 of `T`.
+    ///
+    /// # Safety
+    ///
+    /// The bytes must contain a valid archive with an `L` as its root, and `L` must meet the same
+    /// requirements as for [`access`](Self::access): the same fields as version 0 of `T`, in the
+    /// same order and with the same types, archived with `#[archive_attr(repr(C))]`.
+    #[inline]
+    pub unsafe fn access_root<'a, T>(bytes: &'a [u8]) -> &'a <T::Accessor as ArchiveUnsized>::Archived
+    where
+        L::Archived: 'a,
+        T: Versioned + ?Sized,
+        T::Accessor: ArchiveUnsized,
+        <T::Accessor as ArchiveUnsized>::Archived: Pointee<Metadata = usize>,
+    {
+        Self::access::<T>(archived_root::<L>(bytes))
+    }
+}
+
+/// A serializer that writes archived data to a sink as it is serialized.
+///
+/// Archived data is written from the leaves up, so nothing that has been written needs to be
+/// revisited and the archive never has to be held in memory. Wrap the sink in a `BufWriter` to
+/// write it in chunks.
+#[cfg(feature = "std")]
+pub type StreamSerializer<W> = CompositeSerializer<
+    WriteSerializer<W>,
+    FallbackScratch<HeapScratch<1024>, AllocScratch>,
+    SharedSerializeMap,
+>;
+
+/// Serializes a possibly-unsized value as the unsized root of an archive written to the given sink.
+///
+/// Returns the sink and the position of the root in the archive. The archive can be read back
+/// with [`OwnedArchive`], since reading it requires an aligned buffer.
+#[cfg(feature = "std")]
+pub fn stream_unsized_value<T, W>(value: &T, writer: W) -> Result<(W, usize), <StreamSerializer<W> as Fallible>::Error>
+where
+    T: SerializeUnsized<StreamSerializer<W>> + ?Sized,
+    W: Write,
+{
+    let mut serializer = StreamSerializer::new(WriteSerializer::new(writer), Default::default(), Default::default());
+    let pos = serializer.serialize_unsized_value(value)?;
+    let (writer, _, _) = serializer.into_components();
+    Ok((writer.into_inner(), pos))
+}
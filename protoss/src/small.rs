@@ -0,0 +1,142 @@
+use core::{
+    fmt,
+    marker::PhantomData,
+    mem::{self, align_of, align_of_val, size_of_val, MaybeUninit},
+    ops::{Deref, DerefMut},
+    ptr,
+};
+#[cfg(not(feature = "std"))]
+use ::alloc::boxed::Box;
+use crate::{Proto, Versioned};
+
+#[repr(C, align(16))]
+struct InlineBuffer<const N: usize>(MaybeUninit<[u8; N]>);
+
+enum Repr<T: Versioned, const N: usize> {
+    Inline {
+        buffer: InlineBuffer<N>,
+        version: T::Version,
+    },
+    Boxed(Box<T::Accessor>),
+}
+
+/// An accessor that is stored inline if it fits in `N` bytes, and boxed otherwise.
+///
+/// Accessors with an alignment greater than 16 are always boxed. A `SmallAccessor` is only `Send`
+/// and `Sync` when both `T` and its accessor are.
+pub struct SmallAccessor<T: Versioned, const N: usize> {
+    repr: Repr<T, N>,
+    // The inline buffer holds a T, so SmallAccessor is only Send and Sync if T is
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Versioned, const N: usize> Drop for SmallAccessor<T, N> {
+    fn drop(&mut self) {
+        if let Repr::Inline { .. } = self.repr {
+            unsafe {
+                // SAFETY: the inline accessor
+                // - is valid for reads and writes
+                // - is properly aligned
+                // - points to a value valid for dropping
+                // - will not be accessed after being dropped
+                ptr::drop_in_place(self.deref_mut());
+            }
+        }
+    }
+}
+
+impl<T: Versioned, const N: usize> SmallAccessor<T, N> {
+    /// Creates a new small accessor from a proto, storing it inline if it fits.
+    pub fn new(mut proto: Proto<T>) -> Self {
+        let accessor = proto.access_mut();
+        debug_assert!(
+            align_of_val(accessor) >= align_of::<T>(),
+            "accessors must be at least as aligned as their versioned type",
+        );
+        let size = size_of_val(accessor);
+        if size <= N && align_of_val(accessor) <= align_of::<InlineBuffer<N>>() {
+            let mut buffer = InlineBuffer(MaybeUninit::uninit());
+            unsafe {
+                // SAFETY:
+                // - accessor is valid for reads of `size` bytes
+                // - buffer is valid for writes of `N` bytes, which is at least `size`
+                ptr::copy_nonoverlapping(
+                    accessor as *const T::Accessor as *const u8,
+                    buffer.0.as_mut_ptr().cast::<u8>(),
+                    size,
+                );
+            }
+            let version = proto.version();
+            mem::forget(proto);
+            Self {
+                repr: Repr::Inline { buffer, version },
+                _phantom: PhantomData,
+            }
+        } else {
+            Self {
+                repr: Repr::Boxed(proto.into_boxed_accessor()),
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    /// Returns whether the accessor is stored inline.
+    #[inline]
+    pub fn is_inline(&self) -> bool {
+        matches!(self.repr, Repr::Inline { .. })
+    }
+}
+
+impl<T: Versioned, const N: usize> Deref for SmallAccessor<T, N> {
+    type Target = T::Accessor;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        match self.repr {
+            Repr::Inline { ref buffer, version } => unsafe {
+                // SAFETY:
+                // - buffer holds the bytes of a T::Accessor for version and is suitably aligned
+                // - T::accessor_metadata returns valid metadata for a T::Accessor
+                &*::ptr_meta::from_raw_parts(
+                    buffer.0.as_ptr().cast(),
+                    T::accessor_metadata(version),
+                )
+            },
+            Repr::Boxed(ref accessor) => accessor,
+        }
+    }
+}
+
+impl<T: Versioned, const N: usize> DerefMut for SmallAccessor<T, N> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self.repr {
+            Repr::Inline { ref mut buffer, version } => unsafe {
+                // SAFETY:
+                // - buffer holds the bytes of a T::Accessor for version and is suitably aligned
+                // - T::accessor_metadata returns valid metadata for a T::Accessor
+                &mut *::ptr_meta::from_raw_parts_mut(
+                    buffer.0.as_mut_ptr().cast(),
+                    T::accessor_metadata(version),
+                )
+            },
+            Repr::Boxed(ref mut accessor) => accessor,
+        }
+    }
+}
+
+impl<T: Versioned, const N: usize> From<Proto<T>> for SmallAccessor<T, N> {
+    #[inline]
+    fn from(proto: Proto<T>) -> Self {
+        Self::new(proto)
+    }
+}
+
+impl<T: Versioned, const N: usize> fmt::Debug for SmallAccessor<T, N>
+where
+    T::Accessor: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.deref().fmt(f)
+    }
+}
@@ -0,0 +1,20 @@
+use core::fmt;
+
+/// Describes a field of a versioned type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldDescriptor {
+    /// The name of the field.
+    pub name: &'static str,
+    /// The version the field was added in.
+    pub version: u16,
+    /// The position of the field in [wire order](crate::DescribeFields#wire-order).
+    pub index: usize,
+}
+
+/// Receives the present fields of an accessor one at a time.
+///
+/// Visiting fields does not allocate, which makes it suitable for inspecting large records.
+pub trait FieldVisitor {
+    /// Visits a present field and its value.
+    fn visit_field(&mut self, field: FieldDescriptor, value: &dyn fmt::Debug);
+}
@@ -1,37 +1,99 @@
 use crate::util::*;
-use proc_macro2::{Span, TokenStream};
+use proc_macro2::{Literal, Span, TokenStream};
 use quote::quote;
-use syn::{Error, Generics, Ident, ItemStruct, Meta, punctuated::Punctuated, parse_quote};
+use syn::{Error, Generics, Ident, ItemStruct, Lit, Meta, Path, Token, punctuated::Punctuated, parse_quote};
+
+#[derive(Clone, Copy, Default, PartialEq)]
+enum VersionEncoding {
+    /// The archived metadata is the version number.
+    #[default]
+    Tagged,
+    /// The archived metadata is the archived size, and the version is inferred from it.
+    Size,
+}
 
 #[derive(Default)]
 pub struct Settings {
+    protoss_path: Option<Path>,
+    rkyv_path: Option<Path>,
+    version_encoding: VersionEncoding,
     impl_rkyv: bool,
+    impl_render: bool,
+    impl_to_latest: bool,
+    impl_visit: bool,
+    impl_builder: bool,
+    impl_diff: bool,
+    impl_truncate: bool,
+    impl_root_helpers: bool,
+    impl_check_bytes: bool,
 }
 
 impl Settings {
-    pub fn from_attr(attr: &Option<Meta>) -> Result<Self, Error> {
+    pub fn from_attr(attr: &Punctuated<Meta, Token![,]>) -> Result<Self, Error> {
         let mut result = Self::default();
 
-        if let Some(meta) = attr {
+        for meta in attr.iter() {
             match meta {
                 Meta::Path(path) => {
                     if path.is_ident("rkyv") {
                         result.impl_rkyv = true;
+                    } else if path.is_ident("render") {
+                        result.impl_render = true;
+                    } else if path.is_ident("to_latest") {
+                        result.impl_to_latest = true;
+                    } else if path.is_ident("visit") {
+                        result.impl_visit = true;
+                    } else if path.is_ident("builder") {
+                        result.impl_builder = true;
+                    } else if path.is_ident("diff") {
+                        result.impl_diff = true;
+                    } else if path.is_ident("truncate") {
+                        result.impl_truncate = true;
+                    } else if path.is_ident("root_helpers") {
+                        result.impl_root_helpers = true;
+                    } else if path.is_ident("check_bytes") {
+                        result.impl_check_bytes = true;
                     } else {
                         return Err(Error::new_spanned(path, "unrecognized protoss argument"));
                     }
                 }
+                Meta::NameValue(name_value) if name_value.path.is_ident("crate") => {
+                    result.protoss_path = Some(parse_crate_path(name_value)?);
+                }
+                Meta::NameValue(name_value) if name_value.path.is_ident("rkyv_crate") => {
+                    result.rkyv_path = Some(parse_crate_path(name_value)?);
+                }
+                Meta::NameValue(name_value) if name_value.path.is_ident("version_encoding") => {
+                    result.version_encoding = match &name_value.lit {
+                        Lit::Str(s) if s.value() == "tagged" => VersionEncoding::Tagged,
+                        Lit::Str(s) if s.value() == "size" => VersionEncoding::Size,
+                        _ => return Err(Error::new_spanned(
+                            &name_value.lit,
+                            "version_encoding must be either \"tagged\" or \"size\"",
+                        )),
+                    };
+                }
                 _ => return Err(Error::new_spanned(meta, "protoss arguments must be of the form `protoss(...)`")),
             }
         }
 
+        if result.impl_root_helpers && !result.impl_rkyv {
+            return Err(Error::new(Span::call_site(), "root_helpers requires rkyv"));
+        }
+        if result.impl_check_bytes && !result.impl_rkyv {
+            return Err(Error::new(Span::call_site(), "check_bytes requires rkyv"));
+        }
+
         Ok(result)
     }
 }
 
-pub fn generate(attr: &Option<Meta>, input: &ItemStruct) -> Result<TokenStream, Error> {
+pub fn generate(attr: &Punctuated<Meta, Token![,]>, input: &ItemStruct) -> Result<TokenStream, Error> {
     let settings = Settings::from_attr(attr)?;
 
+    let protoss = &settings.protoss_path.clone().unwrap_or_else(|| parse_quote! { ::protoss });
+    let rkyv = &settings.rkyv_path.clone().unwrap_or_else(|| parse_quote! { ::rkyv });
+
     let name = &input.ident;
     let vis = &input.vis;
     let generics = &input.generics;
@@ -41,21 +103,24 @@ pub fn generate(attr: &Option<Meta>, input: &ItemStruct) -> Result<TokenStream,
 
     let attrs = &input.attrs;
 
-    let rkyv_args = settings.impl_rkyv.then(|| quote! { #[archive_attr(repr(C))] });
+    let check_bytes_args = settings.impl_check_bytes.then(|| quote! { #[archive(check_bytes)] });
+    let rkyv_args = settings.impl_rkyv.then(|| quote! { #[archive_attr(repr(C))] #check_bytes_args });
 
-    let versions = collect_versions(&input.fields)?;
+    let versions = collect_versions(input)?;
 
     let version_structs = versions.iter().map(|(version, fields)| {
         let struct_name = version_struct_name(name, *version);
         let field_names = fields.iter().map(|f| &f.ident).collect::<Vec<_>>();
         let field_types = fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+        let field_vis = fields.iter().map(|f| &f.vis);
+        let version_number = Literal::u16_unsuffixed(*version as u16);
 
         quote! {
             #[repr(C)]
             #(#attrs)*
             #rkyv_args
             #vis struct #struct_name #generics {
-                #(#field_names: #field_types,)*
+                #(#field_vis #field_names: #field_types,)*
                 _phantom: ::core::marker::PhantomData<#name #ty_generics>,
             }
 
@@ -67,6 +132,12 @@ pub fn generate(attr: &Option<Meta>, input: &ItemStruct) -> Result<TokenStream,
                     }
                 }
             }
+
+            impl #impl_generics #protoss::VersionOf for #struct_name #ty_generics #where_clause {
+                type Versioned = #name #ty_generics;
+
+                const VERSION: u16 = #version_number;
+            }
         }
     });
 
@@ -117,10 +188,53 @@ pub fn generate(attr: &Option<Meta>, input: &ItemStruct) -> Result<TokenStream,
         }
     });
 
-    let version_struct = versions.iter().map(|(version, _)| version_struct_name(name, *version));
-
     let parts = parts_struct_name(name);
 
+    // The examples declare the struct again so they compile in the user's crate. They are only
+    // compile-tested when that declaration resolves without the user's crate: the default crate
+    // path, no generics, and field types from the prelude.
+    let examples_compile = settings.protoss_path.is_none()
+        && input.generics.params.is_empty()
+        && versions.iter().all(|(_, fields)| fields.iter().all(|f| is_prelude_default(&f.ty)));
+    let mut parts_doc = vec![
+        format!(" The fields of some version of [`{}`].", name),
+        String::new(),
+        " # Examples".to_string(),
+    ];
+    let mut declaration = vec![" # #[protoss::protoss]".to_string(), format!(" # struct {} {{", name)];
+    for (version, fields) in versions.iter() {
+        for f in fields.iter() {
+            let ty = &f.ty;
+            declaration.push(format!(" #     #[version = {}]", version));
+            declaration.push(format!(" #     {}: {},", f.ident.as_ref().unwrap(), quote!(#ty)));
+        }
+    }
+    declaration.push(" # }".to_string());
+    let mut args = Vec::new();
+    for (version, fields) in versions.iter() {
+        parts_doc.push(String::new());
+        parts_doc.push(format!(" Accessing data of version {}:", version));
+        parts_doc.push(String::new());
+        parts_doc.push(if examples_compile { " ```" } else { " ```ignore" }.to_string());
+        parts_doc.extend(declaration.iter().cloned());
+        args.extend(fields.iter().map(|f| f.ident.as_ref().unwrap().to_string()));
+        for (_, fields) in versions.iter().filter(|(v, _)| v <= version) {
+            for f in fields.iter() {
+                let ty = &f.ty;
+                parts_doc.push(format!(" # let {}: {} = Default::default();", f.ident.as_ref().unwrap(), quote!(#ty)));
+            }
+        }
+        parts_doc.push(format!(" let value = {}::partial_v{}({});", name, version, args.join(", ")));
+        parts_doc.push(" let parts = value.access();".to_string());
+        for (field_version, fields) in versions.iter() {
+            let presence = if field_version <= version { "some" } else { "none" };
+            for f in fields.iter() {
+                parts_doc.push(format!(" assert!(parts.{}().is_{}());", f.ident.as_ref().unwrap(), presence));
+            }
+        }
+        parts_doc.push(" ```".to_string());
+    }
+
     let drop_versions = versions.iter().map(|(version, _)| {
         let version_accessor = version_accessor_mut(*version);
         let version_struct = version_struct_name(name, *version);
@@ -141,50 +255,71 @@ pub fn generate(attr: &Option<Meta>, input: &ItemStruct) -> Result<TokenStream,
         let version_accessor_mut = version_accessor_mut(*version);
         let version_struct = version_struct_name(name, *version);
         let version_field = version_field_name(*version);
+        let version_size_const = version_size_const(*version);
 
         quote! {
+            #[inline]
             unsafe fn #version_accessor_unchecked(&self) -> &#version_struct #ty_generics {
                 let struct_ptr = (self as *const Self).cast::<#name #ty_generics>();
                 let field_ptr = ::core::ptr::addr_of!((*struct_ptr).#version_field);
                 &*field_ptr
             }
 
+            #[inline]
             fn #version_accessor(&self) -> Option<&#version_struct #ty_generics> {
-                unsafe {
-                    let struct_ptr = (self as *const Self).cast::<#name #ty_generics>();
-                    let field_ptr = ::core::ptr::addr_of!((*struct_ptr).#version_field);
-                    let offset = field_ptr.cast::<u8>().offset_from(struct_ptr.cast::<u8>()) as usize;
-                    let size = ::core::mem::size_of::<#version_struct #ty_generics>();
-                    if offset + size > self.bytes.len() {
-                        None
-                    } else {
-                        Some(&*field_ptr)
+                if <#name #ty_generics>::#version_size_const > self.bytes.len() {
+                    None
+                } else {
+                    unsafe {
+                        Some(self.#version_accessor_unchecked())
                     }
                 }
             }
 
+            #[inline]
             unsafe fn #version_accessor_mut_unchecked(&mut self) -> &mut #version_struct #ty_generics {
                 let struct_ptr = (self as *mut Self).cast::<#name #ty_generics>();
                 let field_ptr = ::core::ptr::addr_of_mut!((*struct_ptr).#version_field);
                 &mut *field_ptr
             }
 
+            #[inline]
             fn #version_accessor_mut(&mut self) -> Option<&mut #version_struct #ty_generics> {
-                unsafe {
-                    let struct_ptr = (self as *mut Self).cast::<#name #ty_generics>();
-                    let field_ptr = ::core::ptr::addr_of_mut!((*struct_ptr).#version_field);
-                    let offset = field_ptr.cast::<u8>().offset_from(struct_ptr.cast::<u8>()) as usize;
-                    let size = ::core::mem::size_of::<#version_struct #ty_generics>();
-                    if offset + size > self.bytes.len() {
-                        None
-                    } else {
-                        Some(&mut *field_ptr)
+                if <#name #ty_generics>::#version_size_const > self.bytes.len() {
+                    None
+                } else {
+                    unsafe {
+                        Some(self.#version_accessor_mut_unchecked())
                     }
                 }
             }
         }
     });
 
+    let version_views = versions.iter().map(|(version, _)| {
+        let version_accessor = version_accessor(*version);
+        let version_accessor_mut = version_accessor_mut(*version);
+        let version_struct = version_struct_name(name, *version);
+        let as_version = Ident::new(&format!("as_v{}", version), Span::call_site());
+        let as_version_mut = Ident::new(&format!("as_v{}_mut", version), Span::call_site());
+        let doc = format!("Returns the fields added in version {}, as a [`{}`], if they are present.", version, version_struct);
+        let doc_mut = format!("Returns the fields added in version {}, as a mutable [`{}`], if they are present.", version, version_struct);
+
+        quote! {
+            #[doc = #doc]
+            #[inline]
+            #vis fn #as_version(&self) -> Option<&#version_struct #ty_generics> {
+                self.#version_accessor()
+            }
+
+            #[doc = #doc_mut]
+            #[inline]
+            #vis fn #as_version_mut(&mut self) -> Option<&mut #version_struct #ty_generics> {
+                self.#version_accessor_mut()
+            }
+        }
+    });
+
     let field_accessors = versions.iter().map(|(version, fields)| {
         let version_accessor = version_accessor(*version);
         let version_accessor_mut = version_accessor_mut(*version);
@@ -193,45 +328,639 @@ pub fn generate(attr: &Option<Meta>, input: &ItemStruct) -> Result<TokenStream,
             let vis = &f.vis;
             let name = &f.ident.as_ref().unwrap();
             let name_mut = Ident::new(&format!("{}_mut", name), name.span());
+            let name_set = Ident::new(&format!("set_{}", name), name.span());
             let ty = &f.ty;
 
+            let or_default = match parse_field_args(f).map(|args| args.default) {
+                Ok(default) => default.map(|default| {
+                    let name_or_default = Ident::new(&format!("{}_or_default", name), name.span());
+                    quote! {
+                        #[inline]
+                        #vis fn #name_or_default(&self) -> #ty {
+                            match self.#name() {
+                                Some(value) => ::core::clone::Clone::clone(value),
+                                None => #default,
+                            }
+                        }
+                    }
+                }),
+                Err(e) => Some(e.to_compile_error()),
+            };
+
             quote! {
+                #or_default
+
+                #[inline]
                 #vis fn #name(&self) -> Option<&#ty> {
                     self.#version_accessor().map(|version| &version.#name)
                 }
 
+                #[inline]
                 #vis fn #name_mut(&mut self) -> Option<&mut #ty> {
                     self.#version_accessor_mut().map(|version| &mut version.#name)
                 }
+
+                #[inline]
+                #vis fn #name_set(&mut self, value: #ty) -> Result<(), #ty> {
+                    match self.#name_mut() {
+                        Some(field) => {
+                            *field = value;
+                            Ok(())
+                        }
+                        None => Err(value),
+                    }
+                }
             }
         });
         quote! { #(#result)* }
     });
 
-    let rkyv_impl = settings.impl_rkyv.then(|| {
-        let version_size_const = versions.iter()
-            .map(|(version, _)| version_size_const(*version))
+    let field_layouts = versions.iter().flat_map(|(version, fields)| {
+        let version_struct = version_struct_name(name, *version);
+        let version_field = version_field_name(*version);
+        let ty_generics = &ty_generics;
+        fields.iter().map(move |f| {
+            let name_field = f.ident.as_ref().unwrap();
+            let ty = &f.ty;
+            quote! {
+                #protoss::FieldLayout {
+                    name: ::core::stringify!(#name_field),
+                    offset: ::core::mem::offset_of!(#name #ty_generics, #version_field)
+                        + ::core::mem::offset_of!(#version_struct #ty_generics, #name_field),
+                    size: ::core::mem::size_of::<#ty>(),
+                }
+            }
+        })
+    });
+
+    let render_impl = settings.impl_render.then(|| {
+        let mut render_where_clause = where_clause.clone();
+        let render_fields = versions.iter().flat_map(|(version, fields)| {
+            fields.iter().map(move |f| (*version, *f))
+        }).map(|(version, f)| {
+            let name = f.ident.as_ref().unwrap();
+            let ty = &f.ty;
+            let version = Literal::u16_unsuffixed(version as u16);
+            render_where_clause.predicates.push(parse_quote! { #ty: ::core::fmt::Debug });
+
+            quote! {
+                #protoss::RenderField {
+                    name: ::core::stringify!(#name),
+                    version: #version,
+                    value: self.#name().map(|value| value as &dyn ::core::fmt::Debug),
+                }
+            }
+        }).collect::<Vec<_>>();
+
+        quote! {
+            impl #impl_generics #parts #ty_generics #render_where_clause {
+                /// Renders a table of every field with its version, presence and `Debug` output.
+                #vis fn render(&self) -> ::std::string::String {
+                    #protoss::render_fields(&[#(#render_fields,)*])
+                }
+            }
+        }
+    });
+
+    let visit_impl = settings.impl_visit.then(|| {
+        let mut visit_where_clause = where_clause.clone();
+        let mut index = 0usize;
+        let visit_versions = versions.iter().map(|(version, fields)| {
+            let version_accessor = version_accessor(*version);
+            let version_number = Literal::u16_unsuffixed(*version as u16);
+            let visit_fields = fields.iter().map(|f| {
+                let name = f.ident.as_ref().unwrap();
+                let ty = &f.ty;
+                visit_where_clause.predicates.push(parse_quote! { #ty: ::core::fmt::Debug });
+                let field_index = index;
+                index += 1;
+
+                quote! {
+                    visitor.visit_field(
+                        #protoss::FieldDescriptor {
+                            name: ::core::stringify!(#name),
+                            version: #version_number,
+                            index: #field_index,
+                        },
+                        &version.#name,
+                    );
+                }
+            }).collect::<Vec<_>>();
+
+            quote! {
+                if let Some(version) = self.#version_accessor() {
+                    #(#visit_fields)*
+                } else {
+                    return;
+                }
+            }
+        }).collect::<Vec<_>>();
+
+        quote! {
+            impl #impl_generics #parts #ty_generics #visit_where_clause {
+                /// Passes each present field to the visitor in wire order.
+                ///
+                /// Fields of versions that are not present are skipped.
+                #vis fn visit_fields<__V: #protoss::FieldVisitor + ?Sized>(&self, visitor: &mut __V) {
+                    #(#visit_versions)*
+                }
+            }
+        }
+    });
+
+    let diff_impl = settings.impl_diff.then(|| {
+        let mut diff_where_clause = where_clause.clone();
+        let diff_fields = versions.iter().flat_map(|(version, fields)| {
+            fields.iter().map(move |f| (*version, *f))
+        }).map(|(version, f)| {
+            let name = f.ident.as_ref().unwrap();
+            let ty = &f.ty;
+            let version = Literal::u16_unsuffixed(version as u16);
+            diff_where_clause.predicates.push(parse_quote! { #ty: ::core::cmp::PartialEq + ::core::fmt::Debug });
+
+            quote! {
+                match (self.#name(), other.#name()) {
+                    (Some(left), Some(right)) if left == right => (),
+                    (None, None) => (),
+                    (left, right) => result.push(#protoss::FieldDiff {
+                        name: ::core::stringify!(#name),
+                        version: #version,
+                        left: left.map(|value| value as &dyn ::core::fmt::Debug),
+                        right: right.map(|value| value as &dyn ::core::fmt::Debug),
+                    }),
+                }
+            }
+        }).collect::<Vec<_>>();
+
+        quote! {
+            impl #impl_generics #parts #ty_generics #diff_where_clause {
+                /// Returns the fields that differ between `self` and `other`, in wire order.
+                ///
+                /// The two sides may have different versions. A field present on only one side is
+                /// reported with `None` for the side that lacks it, and fields absent from both
+                /// sides are not reported.
+                #vis fn diff<'__a>(&'__a self, other: &'__a Self) -> #protoss::__private::Vec<#protoss::FieldDiff<'__a>> {
+                    let mut result = #protoss::__private::Vec::new();
+                    #(#diff_fields)*
+                    result
+                }
+            }
+        }
+    });
+
+    let truncate_impl = settings.impl_truncate.then(|| {
+        let mut truncate_where_clause = where_clause.clone();
+        // Versions are checked from newest to oldest, and the first version is never removed
+        let check_versions = versions.iter().skip(1).rev().map(|(version, fields)| {
+            let version_accessor = version_accessor(*version);
+            let version_size_const = version_size_const(*version);
+            let is_default = fields.iter().map(|f| {
+                let name = f.ident.as_ref().unwrap();
+                let ty = &f.ty;
+                truncate_where_clause.predicates.push(parse_quote! {
+                    #ty: ::core::cmp::PartialEq + ::core::default::Default
+                });
+                quote! { version.#name == <#ty as ::core::default::Default>::default() }
+            }).collect::<Vec<_>>();
+
+            quote! {
+                if let Some(version) = self.#version_accessor() {
+                    if !(#(#is_default)&&*) {
+                        return <#name #ty_generics>::#version_size_const;
+                    }
+                }
+            }
+        }).collect::<Vec<_>>();
+        let first_version_size_const = version_size_const(versions[0].0);
+
+        quote! {
+            impl #impl_generics #parts #ty_generics #truncate_where_clause {
+                fn __truncated_size(&self) -> usize {
+                    #(#check_versions)*
+                    <#name #ty_generics>::#first_version_size_const
+                }
+
+                /// Returns an accessor for the smallest version that only leaves out trailing
+                /// versions whose fields all have their default values.
+                pub fn truncated(&self) -> &Self {
+                    unsafe {
+                        // SAFETY: the truncated size is the size of a version that is present
+                        &*#protoss::__private::ptr_meta::from_raw_parts(
+                            (self as *const Self).cast(),
+                            self.__truncated_size(),
+                        )
+                    }
+                }
+            }
+        }
+    });
+
+    let builder_impl = settings.impl_builder.then(|| {
+        let builder = builder_struct_name(name);
+        let all_fields = versions.iter()
+            .flat_map(|(version, fields)| fields.iter().map(move |f| (*version as u16, *f)))
             .collect::<Vec<_>>();
+        let field_names = all_fields.iter().map(|(_, f)| f.ident.as_ref().unwrap()).collect::<Vec<_>>();
+        let field_types = all_fields.iter().map(|(_, f)| &f.ty).collect::<Vec<_>>();
 
-        let version_size = versions.iter().map(|(version, _)| {
-            let struct_name = version_struct_name(name, *version);
-            quote! { ::core::mem::size_of::<#struct_name #ty_generics>() }
+        let setters = all_fields.iter().map(|(version, f)| {
+            let vis = &f.vis;
+            let name = f.ident.as_ref().unwrap();
+            let ty = &f.ty;
+            // Fields from the first version can be set for every target version
+            let check_version = (*version != versions[0].0 as u16).then(|| quote! {
+                if #version > self.version {
+                    return Err(#protoss::BuildError::FieldTooNew {
+                        name: ::core::stringify!(#name),
+                        version: #version,
+                        target: self.version,
+                    });
+                }
+            });
+
+            quote! {
+                #[inline]
+                #vis fn #name(&mut self, value: #ty) -> Result<&mut Self, #protoss::BuildError> {
+                    #check_version
+                    self.#name = Some(value);
+                    Ok(self)
+                }
+            }
+        });
+
+        let build_versions = versions.iter().enumerate().map(|(i, (version, _))| {
+            let partial_constructor = Ident::new(&format!("partial_v{}", version), Span::call_site());
+            let number = Literal::u16_unsuffixed(*version as u16);
+            let args = versions.iter().take(i + 1).flat_map(|(_, fields)| fields.iter()).map(|f| {
+                let name = f.ident.as_ref().unwrap();
+                quote! {
+                    self.#name.ok_or(#protoss::BuildError::MissingField { name: ::core::stringify!(#name) })?
+                }
+            });
+
+            quote! {
+                #number => Ok(#name::#partial_constructor(#(#args,)*))
+            }
+        });
+
+        quote! {
+            #vis struct #builder #generics #where_clause {
+                version: u16,
+                #(#field_names: Option<#field_types>,)*
+                _phantom: ::core::marker::PhantomData<#name #ty_generics>,
+            }
+
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// Returns a builder for the given target version.
+                pub fn builder(version: u16) -> Result<#builder #ty_generics, #protoss::BuildError> {
+                    if Self::VERSIONS.contains(&version) {
+                        Ok(#builder {
+                            version,
+                            #(#field_names: None,)*
+                            _phantom: ::core::marker::PhantomData,
+                        })
+                    } else {
+                        Err(#protoss::BuildError::UnknownVersion { version })
+                    }
+                }
+            }
+
+            impl #impl_generics #builder #ty_generics #where_clause {
+                /// Returns the version the builder targets.
+                #[inline]
+                pub fn version(&self) -> u16 {
+                    self.version
+                }
+
+                #(#setters)*
+
+                /// Builds a value of the target version.
+                pub fn build(self) -> Result<#protoss::Proto<#name #ty_generics>, #protoss::BuildError> {
+                    match self.version {
+                        #(#build_versions,)*
+                        // Builders are only created for known versions
+                        _ => ::core::unreachable!(),
+                    }
+                }
+            }
+        }
+    });
+
+    let to_latest_impl = settings.impl_to_latest.then(|| {
+        let mut to_latest_where_clause = where_clause.clone();
+        let initializers = versions.iter().map(|(version, fields)| {
+            let version_field = version_field_name(*version);
+            let version_struct = version_struct_name(name, *version);
+            let values = fields.iter().map(|f| {
+                let name = f.ident.as_ref().unwrap();
+                let ty = &f.ty;
+
+                // Fields with a declared default use it, the same as their `_or_default` accessor
+                match parse_field_args(f).map(|args| args.default) {
+                    Ok(Some(default)) => {
+                        to_latest_where_clause.predicates.push(parse_quote! { #ty: ::core::clone::Clone });
+                        quote! { self.#name().cloned().unwrap_or_else(|| #default) }
+                    }
+                    Ok(None) => {
+                        to_latest_where_clause.predicates.push(parse_quote! { #ty: ::core::clone::Clone + ::core::default::Default });
+                        quote! { self.#name().cloned().unwrap_or_default() }
+                    }
+                    Err(e) => e.to_compile_error(),
+                }
+            }).collect::<Vec<_>>();
+
+            quote! {
+                #version_field: #version_struct::new(#(#values,)*)
+            }
         }).collect::<Vec<_>>();
 
+        let read_counts = versions.iter().map(|(version, fields)| {
+            let version_accessor = version_accessor(*version);
+            let len = fields.len();
+
+            quote! {
+                if self.#version_accessor().is_some() {
+                    read += #len;
+                }
+            }
+        });
+        let len = versions.iter().map(|(_, fields)| fields.len()).sum::<usize>();
+
+        quote! {
+            impl #impl_generics #parts #ty_generics #to_latest_where_clause {
+                /// Converts the present fields to the latest version of the type.
+                ///
+                /// Fields from versions that are not present are filled in with their
+                /// `#[field(default = ...)]` value if they declare one, and with `Default`
+                /// otherwise.
+                #vis fn to_latest(&self) -> #name #ty_generics {
+                    #name {
+                        #(#initializers,)*
+                    }
+                }
+
+                /// Converts the present fields to the latest version of the type like
+                /// [`to_latest`](Self::to_latest), and records which fields were read and which
+                /// were filled in with defaults.
+                #vis fn to_latest_with_provenance(&self) -> (#name #ty_generics, #protoss::FieldProvenance) {
+                    let mut read = 0;
+                    #(#read_counts)*
+                    (self.to_latest(), #protoss::FieldProvenance::new(read, #len))
+                }
+            }
+        }
+    });
+
+    if let Some((version, _)) = versions.iter().find(|(version, _)| *version > u16::MAX as usize) {
+        return Err(Error::new_spanned(
+            name,
+            format!("version {} is too large, versions must be at most {}", version, u16::MAX),
+        ));
+    }
+
+    let version_number = versions.iter()
+        .map(|(version, _)| Literal::u16_unsuffixed(*version as u16))
+        .collect::<Vec<_>>();
+    let latest_version_number = version_number.last().unwrap();
+
+    let version_size_const = versions.iter()
+        .map(|(version, _)| version_size_const(*version))
+        .collect::<Vec<_>>();
+
+    let metadata_table = (0..=versions.last().unwrap().0)
+        .map(|version| if versions.iter().any(|(v, _)| *v == version) {
+            let size_const = crate::util::version_size_const(version);
+            quote! { Some(Self::#size_const) }
+        } else {
+            quote! { None }
+        })
+        .collect::<Vec<_>>();
+
+    let (field_since_const, field_since): (Vec<_>, Vec<_>) = versions.iter()
+        .flat_map(|(version, fields)| fields.iter().map(move |f| {
+            (field_since_const(f.ident.as_ref().unwrap()), Literal::u16_unsuffixed(*version as u16))
+        }))
+        .unzip();
+
+    let fields_through_version = versions.iter()
+        .scan(0, |count, (_, fields)| {
+            *count += fields.len();
+            Some(*count)
+        })
+        .collect::<Vec<_>>();
+
+    let field_names_through_version = versions.iter()
+        .scan(Vec::new(), |names, (_, fields)| {
+            names.extend(fields.iter().map(|f| f.ident.as_ref().unwrap().to_string()));
+            Some(names.clone())
+        })
+        .map(|names| quote! { &[#(#names,)*] })
+        .collect::<Vec<_>>();
+
+    let accessor_size = versions.iter().map(|(version, _)| {
+        let struct_name = version_struct_name(name, *version);
+        let field_name = version_field_name(*version);
+        quote! {
+            ::core::mem::offset_of!(#name #ty_generics, #field_name)
+                + ::core::mem::size_of::<#struct_name #ty_generics>()
+        }
+    }).collect::<Vec<_>>();
+
+    // Accessors only check the size of the version that a field was added in, so every field must
+    // lie within that version and after all of the earlier versions. This can only be checked in a
+    // const context when the type is not generic.
+    let layout_assertions = generics.params.is_empty().then(|| {
+        let archived = if settings.impl_rkyv { &[false, true][..] } else { &[false][..] };
+        let layout_of = |archived: bool, ty: TokenStream| if archived {
+            quote! { #rkyv::Archived<#ty> }
+        } else {
+            ty
+        };
+
+        let mut assertions = Vec::new();
+        for archived in archived.iter().copied() {
+            let outer = layout_of(archived, quote! { #name });
+            for (index, (version, fields)) in versions.iter().enumerate() {
+                let version_struct = version_struct_name(name, *version);
+                let inner = layout_of(archived, quote! { #version_struct });
+                let version_field = version_field_name(*version);
+
+                if let Some((previous, _)) = index.checked_sub(1).map(|index| &versions[index]) {
+                    let previous_struct = version_struct_name(name, *previous);
+                    let previous_struct = layout_of(archived, quote! { #previous_struct });
+                    let previous_field = version_field_name(*previous);
+                    let message = format!("version {} of {} overlaps version {}", version, name, previous);
+                    assertions.push(quote! {
+                        ::core::assert!(
+                            ::core::mem::offset_of!(#outer, #previous_field)
+                                + ::core::mem::size_of::<#previous_struct>()
+                                <= ::core::mem::offset_of!(#outer, #version_field),
+                            #message,
+                        );
+                    });
+
+                    // Versions are told apart by their size, so each one must be larger than the last
+                    let message = format!(
+                        "version {} of {} is not larger than version {}, so the two can't be told apart",
+                        version, name, previous,
+                    );
+                    assertions.push(quote! {
+                        ::core::assert!(
+                            ::core::mem::offset_of!(#outer, #previous_field)
+                                + ::core::mem::size_of::<#previous_struct>()
+                                < ::core::mem::offset_of!(#outer, #version_field)
+                                    + ::core::mem::size_of::<#inner>(),
+                            #message,
+                        );
+                    });
+                }
+
+                for f in fields.iter() {
+                    let field_name = f.ident.as_ref().unwrap();
+                    let ty = &f.ty;
+                    let field_ty = layout_of(archived, quote! { #ty });
+                    let message = format!("field `{}` of {} is outside of version {}", field_name, name, version);
+                    assertions.push(quote! {
+                        ::core::assert!(
+                            ::core::mem::offset_of!(#inner, #field_name) + ::core::mem::size_of::<#field_ty>()
+                                <= ::core::mem::size_of::<#inner>(),
+                            #message,
+                        );
+                    });
+                }
+            }
+        }
+
+        quote! {
+            const _: () = {
+                #(#assertions)*
+            };
+        }
+    });
+
+    let rkyv_impl = settings.impl_rkyv.then(|| {
+
         let archived_version_size = versions.iter().map(|(version, _)| {
             let struct_name = version_struct_name(name, *version);
-            quote! { ::core::mem::size_of::<::rkyv::Archived<#struct_name #ty_generics>>() }
-        });
+            let field_name = version_field_name(*version);
+            quote! {
+                ::core::mem::offset_of!(#rkyv::Archived<#name #ty_generics>, #field_name)
+                    + ::core::mem::size_of::<#rkyv::Archived<#struct_name #ty_generics>>()
+            }
+        }).collect::<Vec<_>>();
+        let latest_archived_version_size = archived_version_size.last().unwrap();
+        let version_struct_names = versions.iter()
+            .map(|(version, _)| version_struct_name(name, *version))
+            .collect::<Vec<_>>();
 
         let serialize_version = versions.iter().map(|(version, _)| {
-            let version_accessor_unchecked = version_accessor_unchecked(*version);
+            let version_accessor = version_accessor(*version);
+            let resolver = Ident::new(&format!("resolver_{}", version), Span::call_site());
             quote! {
-                ::rkyv::SerializeUnsized::serialize_unsized(
-                    unsafe { self.#version_accessor_unchecked() },
-                    serializer,
-                )
+                let #resolver = match self.#version_accessor() {
+                    Some(version) => Some((version, #rkyv::Serialize::<__S>::serialize(version, serializer)?)),
+                    None => None,
+                };
             }
-        });
+        }).collect::<Vec<_>>();
+
+        let resolve_version = versions.iter().map(|(version, _)| {
+            let resolver = Ident::new(&format!("resolver_{}", version), Span::call_site());
+            let field_name = version_field_name(*version);
+            quote! {
+                if let Some((version, resolver)) = #resolver {
+                    let offset = ::core::mem::offset_of!(#rkyv::Archived<#name #ty_generics>, #field_name);
+                    #rkyv::Archive::resolve(
+                        version,
+                        pos + offset,
+                        resolver,
+                        ::core::ptr::addr_of_mut!((*out).#field_name),
+                    );
+                }
+            }
+        }).collect::<Vec<_>>();
+
+        let archived_version_accessors = versions.iter().zip(archived_version_size.iter()).map(|((version, fields), archived_version_size)| {
+            let version_accessor = version_accessor(*version);
+            let version_struct = version_struct_name(name, *version);
+            let version_field = version_field_name(*version);
+
+            let field_accessors = fields.iter().map(|f| {
+                let vis = &f.vis;
+                let field_name = f.ident.as_ref().unwrap();
+                let ty = &f.ty;
+                quote! {
+                    #[inline]
+                    #vis fn #field_name(&self) -> Option<&#rkyv::Archived<#ty>> {
+                        self.#version_accessor().map(|version| &version.#field_name)
+                    }
+                }
+            }).collect::<Vec<_>>();
+
+            quote! {
+                #[inline]
+                fn #version_accessor(&self) -> Option<&#rkyv::Archived<#version_struct #ty_generics>> {
+                    if #archived_version_size > self.bytes.len() {
+                        None
+                    } else {
+                        unsafe {
+                            let struct_ptr = (self as *const Self).cast::<#rkyv::Archived<#name #ty_generics>>();
+                            Some(&*::core::ptr::addr_of!((*struct_ptr).#version_field))
+                        }
+                    }
+                }
+
+                #(#field_accessors)*
+            }
+        }).collect::<Vec<_>>();
+
+        let (archived_metadata, pointer_metadata, check_metadata, write_metadata) = match settings.version_encoding {
+            VersionEncoding::Tagged => (
+                quote! { #rkyv::Archived<u16> },
+                quote! {
+                    match #rkyv::from_archived!(*archived) {
+                        #(#version_number => #archived_version_size,)*
+                        // Versions newer than the latest known version are read as the latest
+                        version if version > #latest_version_number => #latest_archived_version_size,
+                        // Unknown older versions are corrupt, so none of their fields are read
+                        _ => 0,
+                    }
+                },
+                quote! {
+                    match #rkyv::from_archived!(*archived) {
+                        #(#version_number => Ok(#archived_version_size),)*
+                        version if version > #latest_version_number => if #protoss::__private::STRICT {
+                            Err(#protoss::MetadataError::NewerVersion { version })
+                        } else {
+                            Ok(#latest_archived_version_size)
+                        },
+                        version => Err(#protoss::MetadataError::UnknownVersion { version }),
+                    }
+                },
+                quote! { out.write(#rkyv::to_archived!(version)); },
+            ),
+            VersionEncoding::Size => (
+                quote! { #rkyv::Archived<u32> },
+                quote! {
+                    #rkyv::from_archived!(*archived) as usize
+                },
+                quote! {
+                    let size = #rkyv::from_archived!(*archived) as usize;
+                    if #protoss::__private::STRICT && !(#(size == #archived_version_size)||*) {
+                        Err(#protoss::MetadataError::UnknownSize { size })
+                    } else {
+                        Ok(size)
+                    }
+                },
+                quote! {
+                    let size = match version {
+                        #(#version_number => #archived_version_size,)*
+                        _ => unsafe { ::core::hint::unreachable_unchecked() },
+                    };
+                    out.write(#rkyv::to_archived!(size as u32));
+                },
+            ),
+        };
 
         let archived_parts = archived_parts_struct_name(name);
 
@@ -239,11 +968,11 @@ pub fn generate(attr: &Option<Meta>, input: &ItemStruct) -> Result<TokenStream,
             let mut serialize_where_clause = where_clause.clone();
             for (version, _) in versions.iter() {
                 let struct_name = version_struct_name(name, *version);
-                serialize_where_clause.predicates.push(parse_quote! { #struct_name #ty_generics: ::rkyv::Serialize<__S> })
+                serialize_where_clause.predicates.push(parse_quote! { #struct_name #ty_generics: #rkyv::Serialize<__S> })
             }
 
             let mut serialize_params = Punctuated::default();
-            serialize_params.push(parse_quote! { __S: ::rkyv::ser::Serializer + ?Sized });
+            serialize_params.push(parse_quote! { __S: #rkyv::ser::Serializer + ?Sized });
             for param in input.generics.params.iter() {
                 serialize_params.push(param.clone());
             }
@@ -257,23 +986,220 @@ pub fn generate(attr: &Option<Meta>, input: &ItemStruct) -> Result<TokenStream,
         };
         let (serialize_impl_generics, _, serialize_where_clause) = serialize_generics.split_for_impl();
 
+        // Each present version is checked in place, so the versions past the archived size are never
+        // read
+        let check_bytes_impl = settings.impl_check_bytes.then(|| {
+            let mut check_where_clause = where_clause.clone();
+            let check_versions = versions.iter().zip(archived_version_size.iter()).map(|((version, _), archived_version_size)| {
+                let struct_name = version_struct_name(name, *version);
+                let field_name = version_field_name(*version);
+                let field_name_str = field_name.to_string();
+                check_where_clause.predicates.push(parse_quote! {
+                    #rkyv::Archived<#struct_name #ty_generics>: #rkyv::bytecheck::CheckBytes<__C>
+                });
+
+                quote! {
+                    if #archived_version_size <= len {
+                        <#rkyv::Archived<#struct_name #ty_generics> as #rkyv::bytecheck::CheckBytes<__C>>::check_bytes(
+                            ::core::ptr::addr_of!((*struct_ptr).#field_name),
+                            context,
+                        ).map_err(|error| #rkyv::bytecheck::StructCheckError {
+                            field_name: #field_name_str,
+                            inner: #rkyv::bytecheck::ErrorBox::new(error),
+                        })?;
+                    }
+                }
+            }).collect::<Vec<_>>();
+
+            let mut check_params = Punctuated::default();
+            check_params.push(parse_quote! { __C: ?Sized });
+            for param in input.generics.params.iter() {
+                check_params.push(param.clone());
+            }
+            let check_generics = Generics {
+                lt_token: Some(Default::default()),
+                params: check_params,
+                gt_token: Some(Default::default()),
+                where_clause: Some(check_where_clause),
+            };
+            let (check_impl_generics, _, check_where_clause) = check_generics.split_for_impl();
+
+            quote! {
+                impl #impl_generics #rkyv::validation::LayoutRaw for #archived_parts #ty_generics #where_clause {
+                    #[inline]
+                    fn layout_raw(metadata: usize) -> Result<::core::alloc::Layout, ::core::alloc::LayoutError> {
+                        ::core::alloc::Layout::from_size_align(
+                            metadata,
+                            ::core::mem::align_of::<#rkyv::Archived<#name #ty_generics>>(),
+                        )
+                    }
+                }
+
+                impl #check_impl_generics #rkyv::bytecheck::CheckBytes<__C> for #archived_parts #ty_generics #check_where_clause {
+                    type Error = #rkyv::bytecheck::StructCheckError;
+
+                    unsafe fn check_bytes<'__a>(value: *const Self, context: &mut __C) -> Result<&'__a Self, Self::Error> {
+                        let len = #protoss::__private::ptr_meta::metadata(value);
+                        let struct_ptr = value.cast::<#rkyv::Archived<#name #ty_generics>>();
+                        #(#check_versions)*
+                        Ok(&*value)
+                    }
+                }
+            }
+        });
+
+        let archived_field_ranges = versions.iter().flat_map(|(version, fields)| {
+            let struct_name = version_struct_name(name, *version);
+            let field_name = version_field_name(*version);
+            let ty_generics = &ty_generics;
+            fields.iter().map(move |f| {
+                let name_field = f.ident.as_ref().unwrap();
+                let ty = &f.ty;
+                quote! {
+                    ::core::stringify!(#name_field) => {
+                        let start = ::core::mem::offset_of!(#rkyv::Archived<#name #ty_generics>, #field_name)
+                            + ::core::mem::offset_of!(#rkyv::Archived<#struct_name #ty_generics>, #name_field);
+                        Some(start..start + ::core::mem::size_of::<#rkyv::Archived<#ty>>())
+                    }
+                }
+            })
+        });
+
+        let root_helpers = settings.impl_root_helpers.then(|| quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// Returns the archived parts at the root of the given bytes.
+                ///
+                /// # Safety
+                ///
+                /// The bytes must contain a valid archive with parts of this type as its unsized
+                /// root, such as one written by `to_archived_bytes`.
+                #[inline]
+                pub unsafe fn access_archived(
+                    bytes: &[u8],
+                ) -> &<#parts #ty_generics as #rkyv::ArchiveUnsized>::Archived {
+                    #rkyv::archived_unsized_root::<#parts #ty_generics>(bytes)
+                }
+
+                /// Serializes the parts of the given value into a new buffer, with the parts as the
+                /// unsized root.
+                pub fn to_archived_bytes(
+                    proto: &#protoss::Proto<Self>,
+                ) -> Result<
+                    #rkyv::AlignedVec,
+                    <#rkyv::ser::serializers::AllocSerializer<256> as #rkyv::Fallible>::Error,
+                >
+                where
+                    #parts #ty_generics: #rkyv::SerializeUnsized<#rkyv::ser::serializers::AllocSerializer<256>>,
+                {
+                    use #rkyv::ser::Serializer;
+
+                    let mut serializer = #rkyv::ser::serializers::AllocSerializer::<256>::default();
+                    serializer.serialize_unsized_value(proto.access())?;
+                    Ok(serializer.into_serializer().into_inner())
+                }
+            }
+        });
+
         quote! {
-            #[repr(transparent)]
-            #[derive(::ptr_meta::Pointee)]
+            #root_helpers
+
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// The size of the archived latest version, which is the most bytes archived parts can take.
+                pub const MAX_ARCHIVED_SIZE: usize = ::core::mem::size_of::<#rkyv::Archived<#name #ty_generics>>();
+
+                /// Returns the range of bytes needed to read the given version from archived parts,
+                /// relative to the start of the archived parts.
+                pub fn archived_range_for_version(version: u16) -> Option<::core::ops::Range<usize>> {
+                    match version {
+                        #(#version_number => Some(0..#archived_version_size),)*
+                        _ => None,
+                    }
+                }
+
+                /// Returns the range of bytes occupied by the given field in archived parts,
+                /// relative to the start of the archived parts.
+                ///
+                /// Fields that point to out-of-line data, like strings, also need the data they
+                /// point to.
+                pub fn archived_field_range(field: &str) -> Option<::core::ops::Range<usize>> {
+                    match field {
+                        #(#archived_field_ranges,)*
+                        _ => None,
+                    }
+                }
+            }
+
+            #[repr(C)]
             #vis struct #archived_parts #generics {
-                _phantom: ::core::marker::PhantomData<::rkyv::Archived<#name #ty_generics>>,
+                _phantom: ::core::marker::PhantomData<#rkyv::Archived<#name #ty_generics>>,
+                _align: [#rkyv::Archived<#name #ty_generics>; 0],
                 bytes: [u8],
             }
 
-            impl #impl_generics ::rkyv::ArchivePointee for #archived_parts #ty_generics {
-                type ArchivedMetadata = ::rkyv::Archived<usize>;
+            impl #impl_generics #protoss::__private::ptr_meta::Pointee for #archived_parts #ty_generics #where_clause {
+                type Metadata = usize;
+            }
+
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// Returns the version whose archived parts have the given metadata, if there is
+                /// one.
+                ///
+                /// This is the archived counterpart to `version_for_metadata`.
+                pub fn version_for_archived_metadata(metadata: usize) -> Option<u16> {
+                    #(
+                        if metadata == #archived_version_size {
+                            return Some(#version_number);
+                        }
+                    )*
+                    None
+                }
+            }
+
+            #(
+                impl #impl_generics #protoss::ArchivedVersionOf for #version_struct_names #ty_generics #where_clause {
+                    const ARCHIVED_METADATA: usize = #archived_version_size;
+                }
+            )*
+
+            impl #impl_generics #archived_parts #ty_generics {
+                #(#archived_version_accessors)*
+
+                /// Returns the version of these archived parts, or `None` if their size is not the
+                /// size of a known version.
+                ///
+                /// Parts from a newer producer are read as the latest known version.
+                #[inline]
+                #vis fn version(&self) -> Option<u16> {
+                    <#name #ty_generics>::version_for_archived_metadata(self.bytes.len())
+                }
+
+                /// Returns whether these archived parts are a known version older than the latest
+                /// version, so they could be rewritten as the latest version.
+                #[inline]
+                #vis fn is_stale(&self) -> bool {
+                    self.version().is_some_and(|version| !<#name #ty_generics>::is_latest_known(version))
+                }
+            }
+
+            impl #impl_generics #rkyv::ArchivePointee for #archived_parts #ty_generics {
+                type ArchivedMetadata = #archived_metadata;
 
                 fn pointer_metadata(archived: &Self::ArchivedMetadata) -> usize {
-                    ::rkyv::from_archived!(*archived) as usize
+                    #pointer_metadata
                 }
             }
 
-            impl #impl_generics ::rkyv::ArchiveUnsized for #parts #ty_generics {
+            unsafe impl #impl_generics #protoss::CheckMetadata for #archived_parts #ty_generics {
+                const ALIGN: usize = ::core::mem::align_of::<#rkyv::Archived<#name #ty_generics>>();
+
+                fn check_metadata(archived: &Self::ArchivedMetadata) -> Result<usize, #protoss::MetadataError> {
+                    #check_metadata
+                }
+            }
+
+            #check_bytes_impl
+
+            impl #impl_generics #rkyv::ArchiveUnsized for #parts #ty_generics {
                 type Archived = #archived_parts #ty_generics;
                 type MetadataResolver = ();
 
@@ -281,24 +1207,36 @@ pub fn generate(attr: &Option<Meta>, input: &ItemStruct) -> Result<TokenStream,
                     &self,
                     pos: usize,
                     resolver: Self::MetadataResolver,
-                    out: *mut ::rkyv::Archived<usize>,
+                    out: *mut #archived_metadata,
                 ) {
-                    #(const #version_size_const: usize = #version_size;)*
-                    let len = match self.bytes.len() {
-                        #(#version_size_const => #archived_version_size,)*
+                    let version: u16 = match self.bytes.len() {
+                        #(<#name #ty_generics>::#version_size_const => #version_number,)*
                         _ => unsafe { ::core::hint::unreachable_unchecked() },
                     };
-                    out.write(::rkyv::to_archived!(len as ::rkyv::FixedUsize));
+                    #write_metadata
                 }
             }
 
-            impl #serialize_impl_generics ::rkyv::SerializeUnsized<__S> for #parts #ty_generics #serialize_where_clause {
+            impl #serialize_impl_generics #rkyv::SerializeUnsized<__S> for #parts #ty_generics #serialize_where_clause {
                 fn serialize_unsized(&self, serializer: &mut __S) -> Result<usize, __S::Error> {
-                    #(const #version_size_const: usize = #version_size;)*
-                    match self.bytes.len() {
-                        #(#version_size_const => #serialize_version,)*
+                    let archived_size = match self.bytes.len() {
+                        #(<#name #ty_generics>::#version_size_const => #archived_version_size,)*
                         _ => unsafe { ::core::hint::unreachable_unchecked() },
+                    };
+
+                    #(#serialize_version)*
+
+                    // Each present version is resolved at its offset in the full archived type,
+                    // and only the prefix up to the end of the last present version is written
+                    serializer.align_for::<#rkyv::Archived<#name #ty_generics>>()?;
+                    let pos = serializer.pos();
+                    let mut archived = ::core::mem::MaybeUninit::<#rkyv::Archived<#name #ty_generics>>::zeroed();
+                    let out = archived.as_mut_ptr();
+                    unsafe {
+                        #(#resolve_version)*
+                        serializer.write(::core::slice::from_raw_parts(out.cast::<u8>(), archived_size))?;
                     }
+                    Ok(pos)
                 }
 
                 fn serialize_metadata(&self, serializer: &mut __S) -> Result<(), __S::Error> {
@@ -321,32 +1259,119 @@ pub fn generate(attr: &Option<Meta>, input: &ItemStruct) -> Result<TokenStream,
         impl #impl_generics #name #ty_generics {
             #(
                 #[inline]
-                pub fn #partial_constructors(#partial_args) -> ::protoss::Partial<Self> {
+                pub fn #partial_constructors(#partial_args) -> #protoss::Proto<Self> {
                     unsafe {
                         let mut result = ::core::mem::MaybeUninit::<Self>::uninit();
                         let result_ptr = result.as_mut_ptr();
 
                         #write_versions
 
-                        let size = version_ptr.cast::<u8>().offset_from(result_ptr.cast::<u8>()) as usize
-                            + ::core::mem::size_of::<#version_struct>();
-                        ::protoss::Partial::new_unchecked(result, size)
+                        #protoss::Proto::new_unchecked(result, #version_number)
                     }
                 }
             )*
+
+            /// The versions of this type, in ascending order.
+            pub const VERSIONS: &'static [u16] = &[#(#version_number,)*];
+
+            #(
+                /// The size of an accessor for this version, which is the minimum size that
+                /// has all of the fields of this version.
+                pub const #version_size_const: usize = #accessor_size;
+            )*
+
+            #(
+                /// The version this field was added in.
+                pub const #field_since_const: u16 = #field_since;
+            )*
+
+            const __EVOLUTION_HISTORY: &'static [#protoss::VersionInfo] = &[
+                #(
+                    #protoss::VersionInfo {
+                        version: #version_number,
+                        size: Self::#version_size_const,
+                        fields: #field_names_through_version,
+                    },
+                )*
+            ];
+
+            /// Returns every version of this type and the fields it contains, in ascending order.
+            #[inline]
+            pub const fn evolution_history() -> &'static [#protoss::VersionInfo] {
+                Self::__EVOLUTION_HISTORY
+            }
+
+            /// The accessor metadata for each version, indexed by version number.
+            ///
+            /// Version numbers that are skipped have no metadata.
+            pub const METADATA_TABLE: &'static [Option<usize>] = &[#(#metadata_table,)*];
+
+            /// Returns whether the given version is the latest version known to this build.
+            #[inline]
+            pub fn is_latest_known(version: u16) -> bool {
+                version == <Self as #protoss::Versioned>::LATEST
+            }
+
+            /// Returns whether the given version is newer than any version known to this build,
+            /// which means the data came from a newer producer.
+            #[inline]
+            pub fn is_newer_than_known(version: u16) -> bool {
+                version > <Self as #protoss::Versioned>::LATEST
+            }
+
+            /// Returns how many known versions are newer than the given version, or `None` if the
+            /// version is not known to this build.
+            #[inline]
+            pub fn versions_behind(version: u16) -> Option<usize> {
+                Self::VERSIONS.iter()
+                    .position(|known| *known == version)
+                    .map(|index| Self::VERSIONS.len() - 1 - index)
+            }
+
+            /// Returns the version whose accessor has the given metadata, if there is one.
+            ///
+            /// Archived parts have different metadata, which is looked up with
+            /// `version_for_archived_metadata`.
+            pub fn version_for_metadata(metadata: usize) -> Option<u16> {
+                Self::METADATA_TABLE.iter()
+                    .position(|entry| *entry == Some(metadata))
+                    .map(|version| version as u16)
+            }
         }
 
-        unsafe impl #impl_generics ::protoss::Composite for #name #ty_generics {
-            type Parts = #parts #ty_generics;
+        unsafe impl #impl_generics #protoss::Versioned for #name #ty_generics #where_clause {
+            type Accessor = #parts #ty_generics;
+            type Version = u16;
+
+            const LATEST: u16 = #latest_version_number;
+
+            #[inline]
+            fn accessor_metadata(version: u16) -> usize {
+                match Self::METADATA_TABLE.get(version as usize) {
+                    Some(Some(metadata)) => *metadata,
+                    _ => ::core::panic!("unknown version {} of {}", version, ::core::stringify!(#name)),
+                }
+            }
         }
 
-        #[repr(transparent)]
-        #[derive(::ptr_meta::Pointee)]
+        #(#[doc = #parts_doc])*
+        #[repr(C)]
         #vis struct #parts #generics {
             _phantom: ::core::marker::PhantomData<#name #ty_generics>,
+            _align: [#name #ty_generics; 0],
             bytes: [u8],
         }
 
+        impl #impl_generics #protoss::__private::ptr_meta::Pointee for #parts #ty_generics #where_clause {
+            type Metadata = usize;
+        }
+
+        #layout_assertions
+
+        impl #impl_generics #protoss::DescribeFields for #name #ty_generics #where_clause {
+            const FIELDS: &'static [#protoss::FieldLayout] = &[#(#field_layouts,)*];
+        }
+
         impl #impl_generics Drop for #parts #ty_generics {
             fn drop(&mut self) {
                 unsafe {
@@ -358,9 +1383,62 @@ pub fn generate(attr: &Option<Meta>, input: &ItemStruct) -> Result<TokenStream,
         impl #impl_generics #parts #ty_generics {
             #(#version_accessors)*
 
+            #(#version_views)*
+
+            /// Returns the indices of the present fields, which index into the field layouts from
+            /// `DescribeFields`.
+            ///
+            /// Newer versions only add fields, so the present fields are always a prefix of all
+            /// the fields.
+            #[inline]
+            pub fn fields_present(&self) -> ::core::ops::Range<usize> {
+                let mut count = 0;
+                #(
+                    if <#name #ty_generics>::#version_size_const <= self.bytes.len() {
+                        count = #fields_through_version;
+                    }
+                )*
+                0..count
+            }
+
+            /// Returns an accessor for the given version if it is present.
+            ///
+            /// The returned accessor leaves out the fields of all later versions, so serializing
+            /// it produces the given version.
+            pub fn truncated_to(&self, version: u16) -> Option<&Self> {
+                let size = match version {
+                    #(#version_number => <#name #ty_generics>::#version_size_const,)*
+                    _ => return None,
+                };
+                if size > self.bytes.len() {
+                    None
+                } else {
+                    unsafe {
+                        // SAFETY: the version is present, so the truncated accessor is a valid
+                        // prefix of this one
+                        Some(&*#protoss::__private::ptr_meta::from_raw_parts(
+                            (self as *const Self).cast(),
+                            size,
+                        ))
+                    }
+                }
+            }
+
             #(#field_accessors)*
         }
 
+        #render_impl
+
+        #to_latest_impl
+
+        #visit_impl
+
+        #builder_impl
+
+        #truncate_impl
+
+        #diff_impl
+
         #rkyv_impl
     })
 }
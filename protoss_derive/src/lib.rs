@@ -5,25 +5,32 @@
 #![deny(missing_crate_level_docs)]
 
 mod composite;
+mod transparent;
 mod util;
 
 extern crate proc_macro;
 
-use syn::{ItemStruct, Meta, parse_macro_input};
+use syn::{ItemStruct, Meta, Token, parse_macro_input, punctuated::Punctuated};
 
 /// Generates a composite struct and parts based on the annotated struct.
+///
+/// With `#[protoss(transparent)]`, the annotated struct must instead be a newtype around a
+/// versioned type, and forwards its versions and accessor to the wrapped type.
 #[proc_macro_attribute]
 pub fn protoss(attr: proc_macro::TokenStream, item: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let attr = if attr.is_empty() {
-        None
-    } else {
-        Some(parse_macro_input!(attr as Meta))
-    };
+    let attr = parse_macro_input!(attr with Punctuated::<Meta, Token![,]>::parse_terminated);
 
     let mut input = parse_macro_input!(item as ItemStruct);
     input.generics.make_where_clause();
 
-    match composite::generate(&attr, &input) {
+    let is_transparent = attr.iter().any(|meta| matches!(meta, Meta::Path(path) if path.is_ident("transparent")));
+    let result = if is_transparent {
+        transparent::generate(&attr, &input)
+    } else {
+        composite::generate(&attr, &input)
+    };
+
+    match result {
         Ok(result) => result.into(),
         Err(e) => e.to_compile_error().into(),
     }
@@ -0,0 +1,76 @@
+use crate::util::*;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Error, Fields, ItemStruct, Meta, Token, parse_quote, punctuated::Punctuated};
+
+pub fn generate(attr: &Punctuated<Meta, Token![,]>, input: &ItemStruct) -> Result<TokenStream, Error> {
+    let mut protoss = parse_quote! { ::protoss };
+    for meta in attr.iter() {
+        match meta {
+            Meta::Path(path) if path.is_ident("transparent") => (),
+            Meta::NameValue(name_value) if name_value.path.is_ident("crate") => {
+                protoss = parse_crate_path(name_value)?;
+            }
+            _ => return Err(Error::new_spanned(meta, "transparent protoss types only accept a crate path")),
+        }
+    }
+
+    let name = &input.ident;
+    let attrs = &input.attrs;
+    let vis = &input.vis;
+    let generics = &input.generics;
+    let fields = &input.fields;
+    let semi_token = &input.semi_token;
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let inner = match fields {
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+        _ => return Err(Error::new_spanned(
+            fields,
+            "transparent protoss types must be tuple structs with exactly one field",
+        )),
+    };
+
+    let body = match fields {
+        Fields::Unnamed(_) => quote! { #fields #where_clause #semi_token },
+        _ => unreachable!(),
+    };
+
+    Ok(quote! {
+        #[repr(transparent)]
+        #(#attrs)*
+        #vis struct #name #generics #body
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Wraps some version of the inner type.
+            #[inline]
+            pub fn wrap_proto(proto: #protoss::Proto<#inner>) -> #protoss::Proto<Self> {
+                let (value, version) = proto.into_raw_parts();
+                unsafe {
+                    // SAFETY: Self is a transparent wrapper around the inner type, so the
+                    // initialized fields of value are the initialized fields of the result
+                    let value = ::core::ptr::read(
+                        (&value as *const ::core::mem::MaybeUninit<#inner>)
+                            .cast::<::core::mem::MaybeUninit<Self>>(),
+                    );
+                    #protoss::Proto::new_unchecked(value, version)
+                }
+            }
+        }
+
+        unsafe impl #impl_generics #protoss::Versioned for #name #ty_generics #where_clause {
+            type Accessor = <#inner as #protoss::Versioned>::Accessor;
+            type Version = <#inner as #protoss::Versioned>::Version;
+
+            const LATEST: Self::Version = <#inner as #protoss::Versioned>::LATEST;
+
+            #[inline]
+            fn accessor_metadata(
+                version: Self::Version,
+            ) -> <Self::Accessor as #protoss::__private::ptr_meta::Pointee>::Metadata {
+                <#inner as #protoss::Versioned>::accessor_metadata(version)
+            }
+        }
+    })
+}
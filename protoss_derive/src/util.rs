@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 use proc_macro2::Span;
-use syn::{Attribute, Error, Field, Fields, Ident, Lit, Meta};
+use syn::{Attribute, Error, Expr, Field, Fields, GenericArgument, Ident, ItemStruct, Lit, LitInt, Meta, MetaNameValue, Path, PathArguments, Token, Type};
 
 pub fn parse_version(attr: &Attribute) -> Result<usize, Error> {
     let meta = attr.parse_meta()?;
@@ -13,9 +13,82 @@ pub fn parse_version(attr: &Attribute) -> Result<usize, Error> {
     }
 }
 
-pub fn collect_versions(fields: &Fields) -> Result<Vec<(usize, Vec<&Field>)>, Error> {
+pub fn parse_crate_path(name_value: &MetaNameValue) -> Result<Path, Error> {
+    match &name_value.lit {
+        Lit::Str(path) => path.parse(),
+        _ => Err(Error::new_spanned(&name_value.lit, "crate paths must be string literals")),
+    }
+}
+
+#[derive(Default)]
+pub struct FieldArgs {
+    pub default: Option<Expr>,
+    pub id: Option<usize>,
+    pub since_minor_version: Option<usize>,
+}
+
+pub fn parse_field_args(field: &Field) -> Result<FieldArgs, Error> {
+    fn set<T>(arg: &mut Option<T>, value: T, name: &Ident) -> Result<(), Error> {
+        if arg.replace(value).is_some() {
+            Err(Error::new_spanned(name, format!("field has multiple values for `{}`", name)))
+        } else {
+            Ok(())
+        }
+    }
+
+    let mut result = FieldArgs::default();
+    for attr in field.attrs.iter().filter(|a| a.path.is_ident("field")) {
+        attr.parse_args_with(|input: syn::parse::ParseStream| {
+            while !input.is_empty() {
+                let name = input.parse::<Ident>()?;
+                input.parse::<Token![=]>()?;
+                if name == "default" {
+                    set(&mut result.default, input.parse::<Expr>()?, &name)?;
+                } else if name == "id" {
+                    set(&mut result.id, input.parse::<LitInt>()?.base10_parse()?, &name)?;
+                } else if name == "since_minor_version" {
+                    set(&mut result.since_minor_version, input.parse::<LitInt>()?.base10_parse()?, &name)?;
+                } else {
+                    return Err(Error::new_spanned(name, "unrecognized field argument"));
+                }
+                if !input.is_empty() {
+                    input.parse::<Token![,]>()?;
+                }
+            }
+            Ok(())
+        })?;
+    }
+    Ok(result)
+}
+
+/// Returns whether the type resolves from the prelude and implements `Default`, so examples can
+/// declare values of it without knowing anything about the user's crate.
+pub fn is_prelude_default(ty: &Type) -> bool {
+    const PRIMITIVES: &[&str] = &[
+        "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize",
+        "u8", "u16", "u32", "u64", "u128", "usize", "String",
+    ];
+    const CONTAINERS: &[&str] = &["Box", "Option", "Vec"];
+
+    match ty {
+        Type::Path(path) if path.qself.is_none() && path.path.segments.len() == 1 => {
+            let segment = &path.path.segments[0];
+            let name = segment.ident.to_string();
+            match &segment.arguments {
+                PathArguments::None => PRIMITIVES.contains(&name.as_str()),
+                PathArguments::AngleBracketed(args) if CONTAINERS.contains(&name.as_str()) && args.args.len() == 1 => {
+                    matches!(&args.args[0], GenericArgument::Type(ty) if is_prelude_default(ty))
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+pub fn collect_versions(input: &ItemStruct) -> Result<Vec<(usize, Vec<&Field>)>, Error> {
     let mut version_to_fields = HashMap::new();
-    match fields {
+    match &input.fields {
         Fields::Named(ref fields) => {
             let mut last_version = None;
             for field in fields.named.iter() {
@@ -23,22 +96,66 @@ pub fn collect_versions(fields: &Fields) -> Result<Vec<(usize, Vec<&Field>)>, Er
                     .filter(|a| a.path.is_ident("version"))
                     .map(parse_version)
                     .collect::<Result<Vec<_>, _>>()?;
-                let version = match version_attrs.len() {
-                    0 => last_version.ok_or_else(|| Error::new_spanned(field, "field is not associated with a version"))?,
-                    1 => version_attrs[0],
+                let field_args = parse_field_args(field)?;
+                let version = match (version_attrs.len(), field_args.since_minor_version) {
+                    (0, Some(version)) => {
+                        last_version = Some(version);
+                        version
+                    }
+                    (0, None) => last_version.ok_or_else(|| Error::new_spanned(field, "field is not associated with a version"))?,
+                    (1, None) => {
+                        last_version = Some(version_attrs[0]);
+                        version_attrs[0]
+                    }
+                    (1, Some(_)) => return Err(Error::new_spanned(
+                        field,
+                        "field has both a version attribute and `since_minor_version`; use only one",
+                    )),
                     _ => return Err(Error::new_spanned(field, "field is associated with multiple versions")),
                 };
-                last_version = Some(version);
                 let fields = version_to_fields.entry(version).or_insert(Vec::new());
-                fields.push(field);
+                fields.push((field_args.id, field));
             }
         },
-        _ => return Err(Error::new_spanned(fields, "protoss may only be used on structs with named fields")),
+        fields => return Err(Error::new_spanned(fields, "protoss may only be used on structs with named fields")),
     };
+    if version_to_fields.is_empty() {
+        return Err(Error::new_spanned(input, "protoss structs must declare at least one versioned field"));
+    }
 
     let mut versions = version_to_fields.drain().collect::<Vec<_>>();
     versions.sort_by_key(|(v, _)| *v);
-    Ok(versions)
+
+    // Fields with ids are placed in order of their ids, so ids must increase with the version
+    let mut last_id = None;
+    for (version, fields) in versions.iter_mut() {
+        if fields.iter().any(|(id, _)| id.is_some()) {
+            if let Some((_, field)) = fields.iter().find(|(id, _)| id.is_none()) {
+                return Err(Error::new_spanned(
+                    field,
+                    format!("field has no id, but other fields of version {} do", version),
+                ));
+            }
+            fields.sort_by_key(|(id, _)| *id);
+        }
+        for (id, field) in fields.iter() {
+            if let Some(id) = id {
+                if let Some((last_id, last_field)) = last_id {
+                    if *id <= last_id {
+                        return Err(Error::new_spanned(
+                            field,
+                            format!("field id {} conflicts with field `{}` with id {}", id, last_field, last_id),
+                        ));
+                    }
+                }
+                last_id = Some((*id, field.ident.as_ref().unwrap()));
+            }
+        }
+    }
+
+    Ok(versions.into_iter()
+        .map(|(version, fields)| (version, fields.into_iter().map(|(_, field)| field).collect()))
+        .collect())
 }
 
 pub fn version_struct_name(name: &Ident, version: usize) -> Ident {
@@ -53,6 +170,10 @@ pub fn parts_struct_name(name: &Ident) -> Ident {
     Ident::new(&format!("{}Parts", name), name.span())
 }
 
+pub fn builder_struct_name(name: &Ident) -> Ident {
+    Ident::new(&format!("{}Builder", name), name.span())
+}
+
 pub fn archived_parts_struct_name(name: &Ident) -> Ident {
     Ident::new(&format!("Archived{}Parts", name), name.span())
 }
@@ -76,3 +197,7 @@ pub fn version_accessor_mut(version: usize) -> Ident {
 pub fn version_size_const(version: usize) -> Ident {
     Ident::new(&format!("VERSION_{}_SIZE", version), Span::call_site())
 }
+
+pub fn field_since_const(field: &Ident) -> Ident {
+    Ident::new(&format!("{}_SINCE", field.to_string().to_uppercase()), field.span())
+}
@@ -16,13 +16,21 @@ mod tests {
                 $($field: $ty,)*
             }
 
-            unsafe impl Composite for $composite {
-                type Parts = $parts;
+            unsafe impl Versioned for $composite {
+                type Accessor = $parts;
+                type Version = ();
+
+                const LATEST: () = ();
+
+                fn accessor_metadata(_: ()) -> usize {
+                    core::mem::size_of::<$composite>()
+                }
             }
 
-            #[repr(transparent)]
+            #[repr(C)]
             #[derive(ptr_meta::Pointee)]
             struct $parts {
+                _align: [$composite; 0],
                 bytes: [u8],
             }
 
@@ -74,20 +82,20 @@ mod tests {
         }
     }
 
-    impl_composite! {
+    impl_versioned! {
         struct ExampleV0 as ExampleV0Parts {
             a (a_mut): i32,
         }
     }
 
-    impl_composite! {
+    impl_versioned! {
         struct ExampleV1 as ExampleV1Parts {
             a (a_mut): i32,
             b (b_mut): String,
         }
     }
 
-    impl_composite! {
+    impl_versioned! {
         struct ExampleV2 as ExampleV2Parts {
             a (a_mut): i32,
             b (b_mut): String,
@@ -97,16 +105,16 @@ mod tests {
 
     #[test]
     fn basic_evolution() {
-        let partial_v0 = Partial::new(ExampleV0 {
+        let partial_v0 = Proto::latest(ExampleV0 {
             a: 1,
         });
 
-        let partial_v1 = Partial::new(ExampleV1 {
+        let partial_v1 = Proto::latest(ExampleV1 {
             a: 2,
             b: String::from("foo"),
         });
 
-        let partial_v2 = Partial::new(ExampleV2 {
+        let partial_v2 = Proto::latest(ExampleV2 {
             a: 3,
             b: String::from("bar"),
             c: Some(100),
@@ -114,9 +122,9 @@ mod tests {
 
         use core::mem::transmute;
 
-        let v1_v0 = unsafe { transmute::<&ExampleV0Parts, &ExampleV1Parts>(partial_v0.parts()) };
-        let v1_v1 = partial_v1.parts();
-        let v1_v2 = unsafe { transmute::<&ExampleV2Parts, &ExampleV1Parts>(partial_v2.parts()) };
+        let v1_v0 = unsafe { transmute::<&ExampleV0Parts, &ExampleV1Parts>(partial_v0.access()) };
+        let v1_v1 = partial_v1.access();
+        let v1_v2 = unsafe { transmute::<&ExampleV2Parts, &ExampleV1Parts>(partial_v2.access()) };
 
         assert_eq!(v1_v0.a(), Some(&1));
         assert_eq!(v1_v0.b(), None);
@@ -129,13 +137,13 @@ mod tests {
     }
 
     #[test]
-    fn into_boxed_parts() {
-        let partial_v1 = Partial::new(ExampleV1 {
+    fn into_boxed_accessor() {
+        let partial_v1 = Proto::latest(ExampleV1 {
             a: 2,
             b: String::from("foo"),
         });
 
-        let parts_v1 = partial_v1.into_boxed_parts();
+        let parts_v1 = partial_v1.into_boxed_accessor();
 
         assert_eq!(parts_v1.a(), Some(&2));
         assert_eq!(parts_v1.b(), Some(&String::from("foo")));
@@ -145,13 +153,13 @@ mod tests {
     fn check_drop() {
         use std::rc::Rc;
 
-        impl_composite! {
+        impl_versioned! {
             struct ExampleDropV0 as ExampleDropPartsV0 {
                 a (a_mut): Rc<i32>,
             }
         }
 
-        impl_composite! {
+        impl_versioned! {
             struct ExampleDropV1 as ExampleDropPartsV1 {
                 a (a_mut): Rc<i32>,
                 b (b_mut): Rc<i32>,
@@ -164,14 +172,14 @@ mod tests {
         assert_eq!(Rc::strong_count(&a), 1);
         assert_eq!(Rc::strong_count(&b), 1);
 
-        let partial_v0 = Partial::new(ExampleDropV0 {
+        let partial_v0 = Proto::latest(ExampleDropV0 {
             a: a.clone(),
         });
 
         assert_eq!(Rc::strong_count(&a), 2);
         assert_eq!(Rc::strong_count(&b), 1);
 
-        let partial_v1 = Partial::new(ExampleDropV1 {
+        let partial_v1 = Proto::latest(ExampleDropV1 {
             a: a.clone(),
             b: b.clone(),
         });
@@ -179,12 +187,12 @@ mod tests {
         assert_eq!(Rc::strong_count(&a), 3);
         assert_eq!(Rc::strong_count(&b), 2);
 
-        let parts_v0 = partial_v0.into_boxed_parts();
+        let parts_v0 = partial_v0.into_boxed_accessor();
 
         assert_eq!(Rc::strong_count(&a), 3);
         assert_eq!(Rc::strong_count(&b), 2);
 
-        let parts_v1 = partial_v1.into_boxed_parts();
+        let parts_v1 = partial_v1.into_boxed_accessor();
 
         assert_eq!(Rc::strong_count(&a), 3);
         assert_eq!(Rc::strong_count(&b), 2);
@@ -204,7 +212,7 @@ mod tests {
     fn check_boxed_drop() {
         use std::rc::Rc;
 
-        impl_composite! {
+        impl_versioned! {
             struct ExampleDrop as ExampleDropParts {
                 a (a_mut): Rc<i32>,
             }
@@ -215,13 +223,13 @@ mod tests {
         assert_eq!(Rc::strong_count(&a), 1);
 
         {
-            let partial = Partial::new(ExampleDrop {
+            let partial = Proto::latest(ExampleDrop {
                 a: a.clone(),
             });
 
             assert_eq!(Rc::strong_count(&a), 2);
 
-            let boxed_parts = partial.into_boxed_parts();
+            let boxed_parts = partial.into_boxed_accessor();
 
             assert_eq!(Rc::strong_count(&a), 2);
 
@@ -232,6 +240,12 @@ mod tests {
         assert_eq!(Rc::strong_count(&a), 1);
     }
 
+    #[test]
+    fn check_compile_fail() {
+        let cases = trybuild::TestCases::new();
+        cases.compile_fail("ui/*.rs");
+    }
+
     #[test]
     fn check_derive() {
         use protoss::protoss;
@@ -246,8 +260,8 @@ mod tests {
             pub d: u8,
         }
 
-        let test_v0 = Test::partial_v0(1, 2).into_boxed_parts();
-        let test_v1 = Test::partial_v1(1, 2, 3, 4).into_boxed_parts();
+        let test_v0 = Test::partial_v0(1, 2).into_boxed_accessor();
+        let test_v1 = Test::partial_v1(1, 2, 3, 4).into_boxed_accessor();
 
         assert_eq!(test_v0.a(), test_v1.a());
         assert_eq!(test_v0.b(), test_v1.b());
@@ -256,4 +270,950 @@ mod tests {
         assert_eq!(test_v1.c(), Some(&3));
         assert_eq!(test_v1.d(), Some(&4));
     }
+
+    #[test]
+    fn check_small_accessor() {
+        use protoss::protoss;
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            #[version = 1]
+            pub b: String,
+        }
+
+        let small_v0 = Test::partial_v0(1).into_small_accessor::<64>();
+        assert!(small_v0.is_inline());
+        assert_eq!(small_v0.a(), Some(&1));
+        assert_eq!(small_v0.b(), None);
+
+        let mut small_v1 = Test::partial_v1(1, String::from("foo")).into_small_accessor::<64>();
+        assert!(small_v1.is_inline());
+        assert_eq!(small_v1.b(), Some(&String::from("foo")));
+        assert_eq!(small_v1.set_b(String::from("bar")), Ok(()));
+        assert_eq!(small_v1.b(), Some(&String::from("bar")));
+
+        let mut boxed_v1 = Test::partial_v1(2, String::from("foo")).into_small_accessor::<4>();
+        assert!(!boxed_v1.is_inline());
+        assert_eq!(boxed_v1.a(), Some(&2));
+        assert_eq!(boxed_v1.set_b(String::from("bar")), Ok(()));
+        assert_eq!(boxed_v1.b(), Some(&String::from("bar")));
+    }
+
+    #[test]
+    fn check_small_accessor_over_aligned() {
+        use protoss::protoss;
+
+        #[derive(Debug, PartialEq)]
+        #[repr(align(32))]
+        pub struct HighAlign(u8);
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: HighAlign,
+        }
+
+        let small = Test::partial_v0(HighAlign(3)).into_small_accessor::<256>();
+        assert!(!small.is_inline());
+        assert_eq!(&*small as *const TestParts as *const u8 as usize % 32, 0);
+        assert_eq!(small.a(), Some(&HighAlign(3)));
+    }
+
+    #[test]
+    fn check_small_accessor_drop() {
+        use protoss::protoss;
+        use std::rc::Rc;
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: Rc<String>,
+            #[version = 1]
+            pub b: Rc<String>,
+        }
+
+        let a = Rc::new(String::from("foo"));
+        let b = Rc::new(String::from("bar"));
+
+        let inline = Test::partial_v1(a.clone(), b.clone()).into_small_accessor::<64>();
+        assert!(inline.is_inline());
+        let boxed = Test::partial_v0(a.clone()).into_small_accessor::<4>();
+        assert!(!boxed.is_inline());
+
+        assert_eq!(Rc::strong_count(&a), 3);
+        assert_eq!(Rc::strong_count(&b), 2);
+
+        core::mem::drop(inline);
+
+        assert_eq!(Rc::strong_count(&a), 2);
+        assert_eq!(Rc::strong_count(&b), 1);
+
+        core::mem::drop(boxed);
+
+        assert_eq!(Rc::strong_count(&a), 1);
+    }
+
+    #[test]
+    fn check_render() {
+        use protoss::protoss;
+
+        #[protoss(render)]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 1]
+            pub c: u32,
+            pub d: String,
+        }
+
+        let test_v0 = Test::partial_v0(1, 2).into_boxed_accessor();
+        let test_v1 = Test::partial_v1(1, 2, 300, String::from("foo")).into_boxed_accessor();
+
+        assert_eq!(
+            test_v0.render(),
+            "field  since  present  value\n\
+             a          0  yes      1\n\
+             b          0  yes      2\n\
+             c          1  no\n\
+             d          1  no\n",
+        );
+        assert_eq!(
+            test_v1.render(),
+            "field  since  present  value\n\
+             a          0  yes      1\n\
+             b          0  yes      2\n\
+             c          1  yes      300\n\
+             d          1  yes      \"foo\"\n",
+        );
+    }
+
+    #[test]
+    fn check_high_alignment() {
+        use protoss::protoss;
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: u8,
+            #[version = 1]
+            pub b: u128,
+        }
+
+        let test_v0 = Test::partial_v0(1).into_boxed_accessor();
+        let test_v1 = Test::partial_v1(1, 2).into_boxed_accessor();
+
+        assert_eq!(core::mem::align_of_val(&*test_v0), core::mem::align_of::<Test>());
+        assert_eq!((&*test_v0 as *const TestParts).cast::<u8>() as usize % core::mem::align_of::<u128>(), 0);
+        assert_eq!((&*test_v1 as *const TestParts).cast::<u8>() as usize % core::mem::align_of::<u128>(), 0);
+
+        assert_eq!(test_v0.a(), Some(&1));
+        assert_eq!(test_v0.b(), None);
+        assert_eq!(test_v1.a(), Some(&1));
+        assert_eq!(test_v1.b(), Some(&2));
+    }
+
+    #[test]
+    fn check_send_sync() {
+        use protoss::protoss;
+
+        fn assert_send_sync<T: Send + Sync + ?Sized>() {}
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            #[version = 1]
+            pub b: String,
+        }
+
+        assert_send_sync::<Test>();
+        assert_send_sync::<TestParts>();
+        assert_send_sync::<Box<TestParts>>();
+        assert_send_sync::<Proto<Test>>();
+    }
+
+    #[test]
+    fn check_to_latest() {
+        use protoss::protoss;
+
+        #[protoss(to_latest)]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 1]
+            pub c: u32,
+            pub d: String,
+        }
+
+        let test_v0 = Test::partial_v0(1, 2).into_boxed_accessor();
+        let test_v1 = Test::partial_v1(1, 2, 3, String::from("foo")).into_boxed_accessor();
+
+        let latest_v0 = Proto::latest(test_v0.to_latest());
+        let latest_v1 = Proto::latest(test_v1.to_latest());
+
+        assert_eq!(latest_v0.access().a(), Some(&1));
+        assert_eq!(latest_v0.access().b(), Some(&2));
+        assert_eq!(latest_v0.access().c(), Some(&0));
+        assert_eq!(latest_v0.access().d(), Some(&String::new()));
+        assert_eq!(latest_v1.access().c(), Some(&3));
+        assert_eq!(latest_v1.access().d(), Some(&String::from("foo")));
+
+        let (_, provenance_v0) = test_v0.to_latest_with_provenance();
+        let (_, provenance_v1) = test_v1.to_latest_with_provenance();
+
+        assert_eq!(provenance_v0.read_count(), 2);
+        assert_eq!(provenance_v0.defaulted_count(), 2);
+        assert!(provenance_v0.is_read(1));
+        assert!(provenance_v0.is_defaulted(2));
+        assert_eq!(provenance_v1.read_count(), 4);
+        assert_eq!(provenance_v1.defaulted_count(), 0);
+    }
+
+    #[test]
+    fn check_record_header() {
+        use protoss::{Endianness, RecordHeader, LOG_ALIGNMENT, LOG_FORMAT_VERSION};
+
+        let header = RecordHeader {
+            version: 3,
+            len: 20,
+            endianness: Endianness::Little,
+            checksum: None,
+        };
+        let bytes = header.to_bytes();
+
+        assert_eq!(RecordHeader::parse(&bytes), Some(header));
+        assert_eq!((header.len as usize + header.padding()) % LOG_ALIGNMENT, 0);
+
+        let big = RecordHeader {
+            endianness: Endianness::Big,
+            ..header
+        };
+        assert_eq!(RecordHeader::parse(&big.to_bytes()), Some(big));
+
+        let mut invalid = bytes;
+        invalid[15] = 1;
+        assert_eq!(RecordHeader::parse(&invalid), None);
+
+        let mut unknown_flag = bytes;
+        unknown_flag[6] = 4;
+        assert_eq!(RecordHeader::parse(&unknown_flag), None);
+
+        let mut unflagged_checksum = bytes;
+        unflagged_checksum[8] = 1;
+        assert_eq!(RecordHeader::parse(&unflagged_checksum), None);
+
+        let payload = [0u8; 20];
+        let checked = header.with_checksum(&payload);
+        assert!(checked.checksum.is_some());
+        assert_eq!(RecordHeader::parse(&checked.to_bytes()), Some(checked));
+        assert!(checked.matches(&payload));
+        assert!(!checked.matches(&[1u8; 20]));
+        assert!(!checked.matches(&[0u8; 19]));
+        assert!(header.matches(&[1u8; 20]));
+        assert_eq!(header.with_checksum(b"123456789").checksum, Some(0xcbf4_3926));
+
+        let mut checked_reserved = checked.to_bytes();
+        checked_reserved[12] = 1;
+        assert_eq!(RecordHeader::parse(&checked_reserved), None);
+
+        let mut unknown_format = bytes;
+        unknown_format[7] = LOG_FORMAT_VERSION + 1;
+        assert_eq!(RecordHeader::format_version(&unknown_format), LOG_FORMAT_VERSION + 1);
+        assert_eq!(RecordHeader::parse(&unknown_format), None);
+    }
+
+    #[test]
+    fn check_record_checksums() {
+        use protoss::{LogError, LogReader, LogWriter};
+
+        let mut writer = LogWriter::new(Vec::new()).with_checksums(true);
+        writer.append(1, &[1, 2, 3]).unwrap();
+        let pos = writer.append(1, &[4, 5, 6]).unwrap();
+        let mut log = writer.into_inner();
+
+        let records = LogReader::new(&log).collect::<Result<Vec<_>, _>>().unwrap();
+        assert!(records.iter().all(|record| record.checksum.is_some()));
+
+        let mut reframed = LogWriter::new(Vec::new());
+        reframed.append_record(&records[1]).unwrap();
+        let reframed = reframed.into_inner();
+        assert_eq!(LogReader::new(&reframed).next().unwrap().unwrap().checksum, records[1].checksum);
+
+        log[pos] ^= 1;
+        let mut reader = LogReader::new(&log);
+        assert!(reader.next().unwrap().is_ok());
+        assert_eq!(reader.next(), Some(Err(LogError::ChecksumMismatch { pos })));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn check_reframe_records() {
+        use protoss::{Endianness, LogReader, LogRecord, LogWriter};
+
+        let foreign = LogRecord {
+            version: 2,
+            endianness: Endianness::Big,
+            checksum: None,
+            pos: 0,
+            bytes: &[1, 2, 3, 4, 5],
+        };
+
+        let mut writer = LogWriter::new(Vec::new());
+        writer.append(1, &[6, 7, 8]).unwrap();
+        let pos = writer.append_record(&foreign).unwrap();
+        let log = writer.into_inner();
+
+        let mut reader = LogReader::new(&log);
+        assert_eq!(reader.next().unwrap().unwrap().bytes, &[6, 7, 8]);
+        let record = reader.next().unwrap().unwrap();
+        assert_eq!(record, LogRecord { pos, ..foreign });
+        assert_eq!(record.header(), foreign.header());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn check_setters() {
+        use protoss::protoss;
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            #[version = 1]
+            pub b: String,
+        }
+
+        let mut test_v0 = Test::partial_v0(1).into_boxed_accessor();
+
+        assert_eq!(test_v0.set_a(2), Ok(()));
+        assert_eq!(test_v0.a(), Some(&2));
+        assert_eq!(test_v0.set_b(String::from("foo")), Err(String::from("foo")));
+        assert_eq!(test_v0.b(), None);
+    }
+
+    #[test]
+    fn check_corpus() {
+        use protoss::{Corpus, CorpusEntry};
+
+        let root = std::env::temp_dir().join(format!("protoss_corpus_{}", std::process::id()));
+        let corpus = Corpus::new(&root);
+
+        assert_eq!(corpus.iter().unwrap().count(), 0);
+
+        corpus.record("test", 1, &[1, 2, 3]).unwrap();
+        corpus.record("test", 0, &[4, 5]).unwrap();
+        corpus.record("other", 0, &[6]).unwrap();
+
+        assert_eq!(corpus.get("test", 1).unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(corpus.get("test", 2).unwrap(), None);
+        assert!(corpus.record("../test", 0, &[]).is_err());
+
+        let entries = corpus.iter().unwrap().collect::<Vec<_>>();
+        assert_eq!(
+            entries.iter().map(|e| (e.name.as_str(), e.version)).collect::<Vec<_>>(),
+            vec![("other", 0), ("test", 0), ("test", 1)],
+        );
+        assert_eq!(entries[1].read().unwrap(), vec![4, 5]);
+        assert_eq!(entries[2], CorpusEntry {
+            name: String::from("test"),
+            version: 1,
+            path: root.join("test").join("v1.bin"),
+        });
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn check_replay_corpus() {
+        use protoss::replay_corpus;
+
+        let root = std::env::temp_dir().join(format!("protoss_replay_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("crash-b"), [3, 4]).unwrap();
+        std::fs::write(root.join("crash-a"), [1, 2]).unwrap();
+        std::fs::write(root.join(".gitkeep"), []).unwrap();
+
+        let mut inputs = Vec::new();
+        assert_eq!(replay_corpus(&root, |bytes| inputs.push(bytes.to_vec())).unwrap(), 2);
+        assert_eq!(inputs, vec![vec![1, 2], vec![3, 4]]);
+
+        let result = std::panic::catch_unwind(|| {
+            replay_corpus(&root, |bytes| assert_ne!(bytes, &[3, 4])).unwrap();
+        });
+        assert!(result.is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(replay_corpus(&root, |_| ()).is_err());
+    }
+
+    #[test]
+    fn check_version_table() {
+        use core::mem::size_of;
+        use protoss::protoss;
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 2]
+            pub c: u32,
+        }
+
+        assert_eq!(Test::VERSIONS, &[0, 2]);
+        assert_eq!(Test::LATEST, 2);
+        assert_eq!(Test::accessor_metadata(0), size_of::<TestVersion0>());
+        assert_eq!(Test::accessor_metadata(2), size_of::<Test>());
+        assert_eq!(Test::version_for_metadata(size_of::<TestVersion0>()), Some(0));
+        assert_eq!(Test::version_for_metadata(size_of::<Test>()), Some(2));
+        assert_eq!(Test::version_for_metadata(1), None);
+        assert_eq!(protoss::metadata_for::<TestVersion0>(), size_of::<TestVersion0>());
+        assert_eq!(protoss::metadata_for::<TestVersion2>(), size_of::<Test>());
+        assert_eq!(Test::METADATA_TABLE, &[Some(size_of::<TestVersion0>()), None, Some(size_of::<Test>())]);
+        assert_eq!(Test::VERSION_0_SIZE, size_of::<TestVersion0>());
+        assert_eq!(Test::VERSION_2_SIZE, size_of::<Test>());
+        assert_eq!(Test::B_SINCE, 0);
+        assert_eq!(Test::C_SINCE, 2);
+
+        assert!(Test::is_latest_known(2));
+        assert!(!Test::is_latest_known(0));
+        assert!(Test::is_newer_than_known(3));
+        assert!(!Test::is_newer_than_known(2));
+        assert_eq!(Test::versions_behind(0), Some(1));
+        assert_eq!(Test::versions_behind(2), Some(0));
+        assert_eq!(Test::versions_behind(1), None);
+
+        let test_v0 = Test::partial_v0(1, 2);
+        assert_eq!(test_v0.version(), 0);
+        assert!(!test_v0.is_latest());
+        assert!(test_v0.is_stale());
+        assert_eq!(test_v0.access().c(), None);
+        assert!(!Test::partial_v2(1, 2, 3).is_stale());
+    }
+
+    #[test]
+    fn check_evolution_history() {
+        use core::mem::size_of;
+        use protoss::{protoss, VersionInfo};
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 2]
+            pub c: u32,
+        }
+
+        assert_eq!(
+            Test::evolution_history(),
+            &[
+                VersionInfo {
+                    version: 0,
+                    size: size_of::<TestVersion0>(),
+                    fields: &["a", "b"],
+                },
+                VersionInfo {
+                    version: 2,
+                    size: size_of::<Test>(),
+                    fields: &["a", "b", "c"],
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn check_visit_fields() {
+        use core::fmt::Debug;
+        use protoss::{protoss, FieldDescriptor, FieldVisitor};
+
+        #[protoss(visit)]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 1]
+            pub c: String,
+        }
+
+        struct Collect(Vec<(FieldDescriptor, String)>);
+
+        impl FieldVisitor for Collect {
+            fn visit_field(&mut self, field: FieldDescriptor, value: &dyn Debug) {
+                self.0.push((field, format!("{:?}", value)));
+            }
+        }
+
+        let test_v0 = Test::partial_v0(1, 2);
+        let mut visitor = Collect(Vec::new());
+        test_v0.access().visit_fields(&mut visitor);
+        assert_eq!(visitor.0.len(), 2);
+        assert_eq!(visitor.0[1].0, FieldDescriptor { name: "b", version: 0, index: 1 });
+        assert_eq!(visitor.0[1].1, "2");
+
+        let test_v1 = Test::partial_v1(1, 2, String::from("foo"));
+        let mut visitor = Collect(Vec::new());
+        test_v1.access().visit_fields(&mut visitor);
+        assert_eq!(visitor.0.len(), 3);
+        assert_eq!(visitor.0[2].0, FieldDescriptor { name: "c", version: 1, index: 2 });
+        assert_eq!(visitor.0[2].1, "\"foo\"");
+    }
+
+    #[test]
+    fn check_builder() {
+        use protoss::{protoss, BuildError};
+
+        #[protoss(builder)]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 1]
+            pub c: String,
+        }
+
+        assert_eq!(Test::builder(2).err(), Some(BuildError::UnknownVersion { version: 2 }));
+
+        let mut builder = Test::builder(0).unwrap();
+        builder.a(1).unwrap().b(2).unwrap();
+        assert_eq!(
+            builder.c(String::from("foo")).err(),
+            Some(BuildError::FieldTooNew { name: "c", version: 1, target: 0 }),
+        );
+        let test_v0 = builder.build().unwrap();
+        assert_eq!(test_v0.version(), 0);
+        assert_eq!(test_v0.access().b(), Some(&2));
+
+        let mut builder = Test::builder(1).unwrap();
+        builder.a(1).unwrap().c(String::from("foo")).unwrap();
+        assert_eq!(builder.build().err(), Some(BuildError::MissingField { name: "b" }));
+    }
+
+    #[test]
+    fn check_transparent() {
+        use protoss::protoss;
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            #[version = 1]
+            pub b: String,
+        }
+
+        #[protoss(transparent)]
+        pub struct Wrapper(Test);
+
+        assert_eq!(Wrapper::LATEST, 1);
+
+        let wrapped_v0 = Wrapper::wrap_proto(Test::partial_v0(1));
+        assert_eq!(wrapped_v0.version(), 0);
+        assert_eq!(wrapped_v0.access().a(), Some(&1));
+        assert_eq!(wrapped_v0.access().b(), None);
+
+        let wrapped_v1 = Wrapper::wrap_proto(Test::partial_v1(1, String::from("foo")));
+        let boxed_v1 = wrapped_v1.into_boxed_accessor();
+        assert_eq!(boxed_v1.b(), Some(&String::from("foo")));
+    }
+
+    #[test]
+    fn check_copy_to_box() {
+        use core::mem::{align_of_val, ManuallyDrop};
+        use protoss::copy_to_box;
+
+        #[derive(Debug, PartialEq)]
+        struct Empty;
+
+        #[derive(Debug, PartialEq)]
+        #[repr(align(64))]
+        struct HighAlign(u8);
+
+        let empty = unsafe { copy_to_box(&Empty) };
+        assert_eq!(*empty, Empty);
+
+        let high_align = unsafe { copy_to_box(&HighAlign(3)) };
+        assert_eq!(*high_align, HighAlign(3));
+        assert_eq!(&*high_align as *const HighAlign as usize % 64, 0);
+
+        let slice = unsafe { copy_to_box::<[u16]>(&[1, 2, 3]) };
+        assert_eq!(&*slice, &[1, 2, 3]);
+        assert_eq!(align_of_val(&*slice), 2);
+
+        let string = ManuallyDrop::new(String::from("foo"));
+        let boxed_string = unsafe { copy_to_box::<String>(&string) };
+        assert_eq!(*boxed_string, "foo");
+    }
+
+    #[test]
+    fn check_prefix_compatible() {
+        use protoss::{assert_prefix_compatible, protoss, DescribeFields};
+
+        #[protoss]
+        pub struct Original {
+            #[version = 0]
+            pub a: i32,
+            pub b: u8,
+        }
+
+        #[protoss]
+        pub struct Evolution {
+            #[version = 0]
+            pub a: i32,
+            pub b: u8,
+            #[version = 1]
+            pub c: u64,
+        }
+
+        assert_eq!(Evolution::FIELDS.len(), 3);
+        assert_eq!(Evolution::FIELDS[2].offset, 8);
+        assert_eq!(Evolution::FIELDS[2].size, 8);
+
+        assert_prefix_compatible::<Original, Original>();
+        assert_prefix_compatible::<Original, Evolution>();
+    }
+
+    #[test]
+    #[should_panic = "field 1 is incompatible"]
+    fn check_prefix_incompatible() {
+        use protoss::{assert_prefix_compatible, protoss};
+
+        #[protoss]
+        pub struct Original {
+            #[version = 0]
+            pub a: i32,
+            pub b: u8,
+        }
+
+        #[protoss]
+        pub struct Evolution {
+            #[version = 0]
+            pub a: i32,
+            pub b: u16,
+        }
+
+        assert_prefix_compatible::<Original, Evolution>();
+    }
+
+    #[test]
+    fn check_version_bounds() {
+        use protoss::protoss;
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            #[version = 1]
+            pub b: i32,
+            #[version = 2]
+            pub c: i32,
+        }
+
+        let test_v1 = Test::partial_v1(1, 2);
+        assert!(test_v1.version_at_least(0));
+        assert!(test_v1.version_at_least(1));
+        assert!(!test_v1.version_at_least(2));
+
+        assert_eq!(test_v1.access_at_least(1).and_then(|test| test.b()), Some(&2));
+        assert!(test_v1.access_at_least(2).is_none());
+
+        assert!(test_v1.access_exact(0).is_none());
+        assert_eq!(test_v1.access_exact(1).and_then(|test| test.a()), Some(&1));
+    }
+
+    #[test]
+    fn check_as_latest() {
+        use protoss::protoss;
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            #[version = 1]
+            pub b: String,
+        }
+
+        let mut test_v0 = Test::partial_v0(1);
+        assert!(test_v0.as_latest().is_none());
+        assert!(test_v0.as_latest_mut().is_none());
+
+        let mut test_v1 = Test::partial_v1(1, String::from("foo"));
+        assert_eq!(test_v1.as_latest().unwrap().version_1.b, "foo");
+
+        test_v1.as_latest_mut().unwrap().version_0.a = 2;
+        test_v1.as_latest_mut().unwrap().version_1.b.push_str("bar");
+        assert_eq!(test_v1.access().a(), Some(&2));
+        assert_eq!(test_v1.access().b(), Some(&String::from("foobar")));
+
+        let latest = unsafe { test_v1.as_latest_unchecked() };
+        assert_eq!(latest.version_0.a, 2);
+        unsafe {
+            test_v1.as_latest_mut_unchecked().version_0.a = 3;
+        }
+        assert_eq!(test_v1.access().a(), Some(&3));
+    }
+
+    #[test]
+    fn check_diff() {
+        use protoss::protoss;
+
+        #[protoss(diff)]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 1]
+            pub c: String,
+        }
+
+        let left = Test::partial_v0(1, 2);
+        let right = Test::partial_v1(1, 3, String::from("foo"));
+
+        let diffs = left.access().diff(right.access());
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].name, "b");
+        assert!(diffs[0].is_changed());
+        assert_eq!(format!("{:?}", diffs[0].right), "Some(3)");
+        assert_eq!(diffs[1].name, "c");
+        assert_eq!(diffs[1].version, 1);
+        assert!(diffs[1].is_right_only());
+
+        assert!(left.access().diff(left.access()).is_empty());
+    }
+
+    #[test]
+    fn check_or_default() {
+        use protoss::protoss;
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            #[version = 1]
+            #[field(default = 42)]
+            pub b: i32,
+            #[field(default = String::from("none"))]
+            pub c: String,
+        }
+
+        let test_v0 = Test::partial_v0(1);
+        assert_eq!(test_v0.access().b_or_default(), 42);
+        assert_eq!(test_v0.access().c_or_default(), "none");
+
+        let test_v1 = Test::partial_v1(1, 2, String::from("foo"));
+        assert_eq!(test_v1.access().b_or_default(), 2);
+        assert_eq!(test_v1.access().c_or_default(), "foo");
+    }
+
+    #[test]
+    fn check_to_latest_field_defaults() {
+        use protoss::protoss;
+
+        #[derive(Clone)]
+        pub struct NoDefault(u32);
+
+        #[protoss(to_latest)]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            #[version = 1]
+            #[field(default = 42)]
+            pub b: i32,
+            pub c: String,
+            #[field(default = NoDefault(7))]
+            pub d: NoDefault,
+        }
+
+        let latest = Test::partial_v0(1).access().to_latest();
+        assert_eq!(latest.version_1.b, 42);
+        assert_eq!(latest.version_1.c, "");
+        assert_eq!(latest.version_1.d.0, 7);
+
+        let latest = Test::partial_v1(1, 2, String::from("foo"), NoDefault(3)).access().to_latest();
+        assert_eq!(latest.version_1.b, 2);
+        assert_eq!(latest.version_1.d.0, 3);
+    }
+
+    #[test]
+    fn check_field_ids() {
+        use protoss::{protoss, DescribeFields};
+
+        #[protoss]
+        pub struct Test {
+            #[field(id = 2, since_minor_version = 1)]
+            pub c: u32,
+            #[field(id = 1, since_minor_version = 0)]
+            pub b: i32,
+            #[field(id = 0, since_minor_version = 0)]
+            pub a: i32,
+        }
+
+        assert_eq!(Test::VERSIONS, &[0, 1]);
+        assert_eq!(
+            Test::FIELDS.iter().map(|f| f.name).collect::<Vec<_>>(),
+            vec!["a", "b", "c"],
+        );
+
+        let test = Test::partial_v0(1, 2);
+        assert_eq!(test.access().a(), Some(&1));
+        assert_eq!(test.access().b(), Some(&2));
+        assert_eq!(test.access().c(), None);
+    }
+
+    #[test]
+    fn check_mixed_version_attributes() {
+        use protoss::protoss;
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            #[field(since_minor_version = 1)]
+            pub b: i32,
+            pub c: i32,
+            #[version = 2]
+            pub d: i32,
+        }
+
+        assert_eq!(Test::VERSIONS, &[0, 1, 2]);
+        assert_eq!(Test::B_SINCE, 1);
+        assert_eq!(Test::C_SINCE, 1);
+        assert_eq!(Test::D_SINCE, 2);
+
+        let test = Test::partial_v1(1, 2, 3);
+        assert_eq!(test.access().b(), Some(&2));
+        assert_eq!(test.access().c(), Some(&3));
+        assert_eq!(test.access().d(), None);
+    }
+
+    #[test]
+    fn check_version_views() {
+        use protoss::protoss;
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 1]
+            pub c: String,
+        }
+
+        let mut test_v0 = Test::partial_v0(1, 2);
+        let v0 = test_v0.access().as_v0().unwrap();
+        assert_eq!((v0.a, v0.b), (1, 2));
+        assert!(test_v0.access().as_v1().is_none());
+
+        test_v0.access_mut().as_v0_mut().unwrap().b = 3;
+        assert_eq!(test_v0.access().b(), Some(&3));
+
+        let test_v1 = Test::partial_v1(1, 2, String::from("foo"));
+        assert_eq!(test_v1.access().as_v1().unwrap().c, "foo");
+    }
+
+    #[test]
+    fn check_truncated() {
+        use protoss::protoss;
+
+        #[protoss(truncate)]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            #[version = 1]
+            pub b: i32,
+            #[version = 2]
+            pub c: String,
+        }
+
+        let sparse = Test::partial_v2(1, 2, String::new());
+        let truncated = sparse.access().truncated();
+        assert_eq!(truncated.b(), Some(&2));
+        assert_eq!(truncated.c(), None);
+
+        let empty = Test::partial_v2(1, 0, String::new());
+        assert_eq!(empty.access().truncated().b(), None);
+        assert_eq!(empty.access().truncated().a(), Some(&1));
+
+        let full = Test::partial_v2(1, 0, String::from("foo"));
+        assert_eq!(full.access().truncated().c(), Some(&String::from("foo")));
+
+        assert_eq!(full.access().truncated_to(0).unwrap().b(), None);
+        assert!(Test::partial_v0(1).access().truncated_to(1).is_none());
+        assert!(full.access().truncated_to(3).is_none());
+    }
+
+    #[test]
+    fn check_crate_path() {
+        mod facade {
+            pub use ::protoss as inner;
+        }
+
+        #[facade::inner::protoss(crate = "facade::inner", to_latest)]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            #[version = 1]
+            pub b: u32,
+        }
+
+        #[facade::inner::protoss(crate = "facade::inner", transparent)]
+        pub struct Wrapper(Test);
+
+        assert_eq!(Test::LATEST, 1);
+        assert_eq!(Wrapper::LATEST, 1);
+        assert_eq!(Test::partial_v0(1).access().to_latest().version_1.b, 0);
+    }
+
+    #[test]
+    fn check_fields_present() {
+        use protoss::{protoss, DescribeFields};
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 1]
+            pub c: String,
+        }
+
+        let test_v0 = Test::partial_v0(1, 2);
+        assert_eq!(test_v0.access().fields_present(), 0..2);
+
+        let test_v1 = Test::partial_v1(1, 2, String::new());
+        let present = test_v1.access().fields_present()
+            .map(|index| Test::FIELDS[index].name)
+            .collect::<Vec<_>>();
+        assert_eq!(present, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn check_truncated_fields_present() {
+        use protoss::protoss;
+
+        #[protoss]
+        pub struct Test {
+            #[version = 0]
+            pub a: u8,
+            #[version = 1]
+            pub b: u8,
+            pub c: u8,
+            #[version = 3]
+            pub d: u8,
+        }
+
+        let full = Test::partial_v3(1, 2, 3, 4);
+        let expected = [(0, 0..1), (1, 0..3), (3, 0..4)];
+        assert_eq!(Test::VERSIONS, expected.clone().map(|(version, _)| version));
+        for (version, fields) in expected {
+            let truncated = full.access().truncated_to(version).unwrap();
+            assert_eq!(truncated.fields_present(), fields);
+            assert_eq!(truncated.b().is_some(), version >= 1);
+            assert_eq!(truncated.d().is_some(), version >= 3);
+        }
+        assert!(full.access().truncated_to(2).is_none());
+    }
 }
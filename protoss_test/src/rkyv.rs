@@ -14,8 +14,8 @@ struct Test {
 
 #[cfg(test)]
 pub mod tests {
-    use protoss::{Partial, protoss};
-    use rkyv::{archived_root, Archive, Deserialize, Serialize, ser::{serializers::AllocSerializer, Serializer}};
+    use protoss::protoss;
+    use rkyv::{archived_unsized_root, Archive, Archived, Deserialize, Serialize, ser::{serializers::AllocSerializer, Serializer}};
 
     type DefaultSerializer = AllocSerializer<256>;
 
@@ -35,13 +35,720 @@ pub mod tests {
         let test_v0 = Test::partial_v0(1, 2);
 
         let mut serializer = DefaultSerializer::default();
-        serializer.serialize_value(&test_v0).unwrap();
+        serializer.serialize_unsized_value(test_v0.access()).unwrap();
         let buf = serializer.into_serializer().into_inner();
 
-        let archived_v0 = unsafe { archived_root::<Partial<Test>>(&buf) };
-        assert_eq!(archived_v0.a(), test_v0.parts().a());
-        assert_eq!(archived_v0.b(), test_v0.parts().b());
+        let archived_v0 = unsafe { archived_unsized_root::<TestParts>(&buf) };
+        assert_eq!(archived_v0.a(), test_v0.access().a());
+        assert_eq!(archived_v0.b(), test_v0.access().b());
         assert_eq!(archived_v0.c(), None);
         assert_eq!(archived_v0.d(), None);
     }
+
+    #[test]
+    fn max_archived_size() {
+        #[protoss(rkyv)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 1]
+            pub c: u32,
+            pub d: u8,
+        }
+
+        const BUFFER: [u8; Test::MAX_ARCHIVED_SIZE] = [0; Test::MAX_ARCHIVED_SIZE];
+
+        assert_eq!(BUFFER.len(), 16);
+    }
+
+    #[test]
+    fn unaligned_access() {
+        use protoss::AlignedBytes;
+        use rkyv::{archived_unsized_root, AlignedVec};
+
+        #[protoss(rkyv)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 1]
+            pub c: u32,
+            pub d: u8,
+        }
+
+        let test_v0 = Test::partial_v0(1, 2);
+
+        let mut serializer = DefaultSerializer::default();
+        serializer.serialize_unsized_value(test_v0.access()).unwrap();
+        let buf = serializer.into_serializer().into_inner();
+
+        let mut unaligned = AlignedVec::new();
+        unaligned.push(0);
+        unaligned.extend_from_slice(&buf);
+
+        let aligned = AlignedBytes::for_type::<Archived<Test>>(&unaligned[1..]);
+        assert!(!aligned.is_borrowed());
+
+        let archived_v0 = unsafe { archived_unsized_root::<TestParts>(&aligned) };
+        assert_eq!(archived_v0.a(), test_v0.access().a());
+        assert_eq!(archived_v0.b(), test_v0.access().b());
+
+        assert!(AlignedBytes::for_type::<Archived<Test>>(&buf).is_borrowed());
+    }
+
+    #[test]
+    fn record_log() {
+        use protoss::{Endianness, LogError, LogReader, LogWriter, RecordHeader, LOG_FORMAT_VERSION, LOG_HEADER_SIZE};
+        use rkyv::{archived_unsized_root, AlignedVec};
+
+        #[protoss(rkyv)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 1]
+            pub c: u32,
+            pub d: u8,
+        }
+
+        let first = Test::partial_v0(1, 2);
+        let second = Test::partial_v0(3, 4);
+
+        let mut writer = LogWriter::new(AlignedVec::new());
+        for parts in [first.access(), second.access()] {
+            let mut serializer = DefaultSerializer::default();
+            serializer.serialize_unsized_value(parts).unwrap();
+            let buf = serializer.into_serializer().into_inner();
+            writer.append(0, &buf).unwrap();
+        }
+        let log = writer.into_inner();
+
+        let records = LogReader::new(&log).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].version, 0);
+        assert_eq!(records[1].version, 0);
+        assert!(records[0].endianness.is_native());
+
+        let archived_first = unsafe { archived_unsized_root::<TestParts>(records[0].bytes) };
+        assert_eq!(archived_first.a(), first.access().a());
+        assert_eq!(archived_first.c(), None);
+        let archived_second = unsafe { archived_unsized_root::<TestParts>(records[1].bytes) };
+        assert_eq!(archived_second.a(), second.access().a());
+        assert_eq!(archived_second.b(), second.access().b());
+
+        let truncated = &log[..log.len() - 1];
+        let mut reader = LogReader::new(truncated);
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(reader.next(), Some(Err(LogError::TruncatedPayload { .. }))));
+        assert!(reader.next().is_none());
+
+        let len = records[0].bytes.len();
+        let mut reader = LogReader::new(&log).with_max_len(len - 1);
+        assert_eq!(
+            reader.next(),
+            Some(Err(LogError::PayloadTooLarge { pos: records[0].pos, len, max_len: len - 1 })),
+        );
+        assert!(reader.next().is_none());
+        assert_eq!(LogReader::new(&log).with_max_len(len).count(), 2);
+
+        let mut hostile = AlignedVec::new();
+        hostile.extend_from_slice(&RecordHeader { version: 0, len: u32::MAX, endianness: Endianness::NATIVE, checksum: None }.to_bytes());
+        assert_eq!(
+            LogReader::new(&hostile).next(),
+            Some(Err(LogError::TruncatedPayload { pos: LOG_HEADER_SIZE, len: u32::MAX as usize })),
+        );
+
+        let mut future = log.clone();
+        future[7] = LOG_FORMAT_VERSION + 1;
+        assert_eq!(
+            LogReader::new(&future).next(),
+            Some(Err(LogError::UnsupportedFormat { pos: 0, format_version: LOG_FORMAT_VERSION + 1 })),
+        );
+    }
+
+    #[test]
+    fn foreign_endian_record() {
+        use core::mem::size_of;
+        use protoss::{checked_unsized_root, Endianness, LogReader, LogRecord, LogWriter, MetadataError};
+        use rkyv::{AlignedVec, RelPtr};
+
+        #[protoss(rkyv)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: u8,
+            #[version = 1]
+            pub c: f32,
+        }
+
+        let test = Test::partial_v1(1, 2, 3.5);
+        let mut serializer = DefaultSerializer::default();
+        serializer.serialize_unsized_value(test.access()).unwrap();
+        let mut buf = serializer.into_serializer().into_inner();
+
+        let mut writer = LogWriter::new(AlignedVec::new());
+        writer.append(1, &buf).unwrap();
+
+        // Simulate a producer with the other byte order by reversing the bytes of each field, and
+        // of the offset and metadata of the root pointer
+        let archived = unsafe { archived_unsized_root::<TestParts>(&buf) };
+        let root = archived as *const ArchivedTestParts as *const u8 as usize - buf.as_ptr() as usize;
+        let rel_ptr_pos = buf.len() - size_of::<RelPtr<ArchivedTestParts>>();
+        let rel_ptr = unsafe { &*buf.as_ptr().add(rel_ptr_pos).cast::<RelPtr<ArchivedTestParts>>() };
+        let offset = rel_ptr.base() as usize - buf.as_ptr() as usize;
+        let metadata = rel_ptr.metadata() as *const _ as *const u8 as usize - buf.as_ptr() as usize;
+        let mut swapped = vec![
+            offset..offset + size_of::<Archived<isize>>(),
+            metadata..metadata + size_of::<Archived<u16>>(),
+        ];
+        for field in ["a", "b", "c"] {
+            let range = Test::archived_field_range(field).unwrap();
+            swapped.push(root + range.start..root + range.end);
+        }
+        for range in swapped {
+            buf[range].reverse();
+        }
+
+        let foreign = match Endianness::archived() {
+            Endianness::Little => Endianness::Big,
+            Endianness::Big => Endianness::Little,
+        };
+        writer.append_record(&LogRecord { version: 1, endianness: foreign, checksum: None, pos: 0, bytes: &buf }).unwrap();
+        let log = writer.into_inner();
+
+        let records = LogReader::new(&log).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records[0].endianness, Endianness::archived());
+        assert_eq!(records[1].endianness, foreign);
+
+        let native = unsafe { records[0].checked_root::<TestParts>().unwrap() };
+        assert_eq!(records[0].endianness.read(native.a().unwrap()), 1);
+
+        // Reading the swapped root pointer in the native byte order sees version 256 and puts the
+        // root out of bounds
+        assert_eq!(
+            unsafe { checked_unsized_root::<TestParts>(records[1].bytes) }.err(),
+            Some(if cfg!(feature = "strict") {
+                MetadataError::NewerVersion { version: 256 }
+            } else {
+                MetadataError::OutOfBounds
+            }),
+        );
+
+        let archived = unsafe { records[1].checked_root::<TestParts>().unwrap() };
+        assert_eq!(archived.version(), Some(1));
+        assert_ne!(archived.a(), Some(&1));
+        assert_eq!(foreign.read(archived.a().unwrap()), 1);
+        assert_eq!(foreign.read(archived.b().unwrap()), 2);
+        assert_eq!(foreign.read(archived.c().unwrap()), 3.5);
+        assert_eq!(Endianness::archived().read(&1i32), 1);
+    }
+
+    #[test]
+    fn owned_record() {
+        use protoss::{Endianness, LogError, LogReader, LogWriter, OwnedRecord, RecordHeader, LOG_HEADER_SIZE};
+        use rkyv::AlignedVec;
+
+        let header = RecordHeader {
+            version: 1,
+            len: 3,
+            endianness: Endianness::Big,
+            checksum: None,
+        };
+        assert!(OwnedRecord::from_parts(header, &[1, 2]).is_none());
+        assert!(OwnedRecord::from_parts(header.with_checksum(&[1, 2, 3]), &[1, 2, 4]).is_none());
+
+        let record = OwnedRecord::from_parts(header, &[1, 2, 3]).unwrap();
+        assert_eq!(record.header(), header);
+        assert_eq!(record.payload(), &[1, 2, 3]);
+        assert_eq!(record.as_bytes().len(), LOG_HEADER_SIZE + 16);
+        assert_eq!(record.as_bytes().as_ptr() as usize & 15, 0);
+
+        let read = LogReader::new(record.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(read, record.as_record());
+
+        let mut writer = LogWriter::new(AlignedVec::new());
+        writer.append_record(&record.as_record()).unwrap();
+        writer.append(2, &[4, 5]).unwrap();
+        let log = writer.into_inner();
+
+        let copy = OwnedRecord::from_bytes(&log[1..][..log.len() - 1]);
+        assert_eq!(copy.unwrap_err(), LogError::InvalidHeader { pos: 0 });
+        let copy = OwnedRecord::from_bytes(&log).unwrap();
+        assert_eq!(copy.as_bytes(), record.as_bytes());
+        assert_eq!(
+            OwnedRecord::from_bytes(&log[..LOG_HEADER_SIZE - 1]).unwrap_err(),
+            LogError::TruncatedHeader { pos: 0 },
+        );
+        assert_eq!(
+            OwnedRecord::from_bytes(&log[..LOG_HEADER_SIZE + 2]).unwrap_err(),
+            LogError::TruncatedPayload { pos: LOG_HEADER_SIZE, len: 3 },
+        );
+
+        let checked = OwnedRecord::from_parts(header.with_checksum(&[1, 2, 3]), &[1, 2, 3]).unwrap();
+        let mut corrupt = checked.as_bytes().to_vec();
+        corrupt[LOG_HEADER_SIZE] = 0;
+        assert_eq!(
+            OwnedRecord::from_bytes(&corrupt).unwrap_err(),
+            LogError::ChecksumMismatch { pos: LOG_HEADER_SIZE },
+        );
+
+        let mut unaligned = vec![0];
+        unaligned.extend_from_slice(&log);
+        let copy = OwnedRecord::from_bytes(&unaligned[1..]).unwrap();
+        assert_eq!(copy.as_bytes(), record.as_bytes());
+    }
+
+    #[test]
+    fn version_encodings() {
+        use core::mem::{size_of, size_of_val};
+        use rkyv::archived_unsized_root;
+
+        #[protoss(rkyv)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Tagged {
+            #[version = 0]
+            pub a: i32,
+            #[version = 1]
+            pub b: u32,
+        }
+
+        #[protoss(rkyv, version_encoding = "size")]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct SizeInferred {
+            #[version = 0]
+            pub a: i32,
+            #[version = 1]
+            pub b: u32,
+        }
+
+        let tagged_v0 = Tagged::partial_v0(1);
+        let mut serializer = DefaultSerializer::default();
+        serializer.serialize_unsized_value(tagged_v0.access()).unwrap();
+        let buf = serializer.into_serializer().into_inner();
+        let archived_tagged_v0 = unsafe { archived_unsized_root::<TaggedParts>(&buf) };
+        assert_eq!(size_of_val(archived_tagged_v0), size_of::<Archived<TaggedVersion0>>());
+
+        let sized_v0 = SizeInferred::partial_v0(1);
+        let mut serializer = DefaultSerializer::default();
+        serializer.serialize_unsized_value(sized_v0.access()).unwrap();
+        let buf = serializer.into_serializer().into_inner();
+        let archived_sized_v0 = unsafe { archived_unsized_root::<SizeInferredParts>(&buf) };
+        assert_eq!(size_of_val(archived_sized_v0), size_of::<Archived<SizeInferredVersion0>>());
+    }
+
+    #[test]
+    fn owned_archive() {
+        use core::mem::{size_of, size_of_val};
+        use protoss::OwnedArchive;
+
+        #[protoss(rkyv)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 1]
+            pub c: u32,
+        }
+
+        let test_v0 = Test::partial_v0(1, 2);
+        let mut serializer = DefaultSerializer::default();
+        serializer.serialize_unsized_value(test_v0.access()).unwrap();
+        let buf = serializer.into_serializer().into_inner().to_vec();
+
+        let owned = unsafe { OwnedArchive::<TestParts>::from_vec(buf.clone()) };
+        assert_eq!(owned.as_bytes(), buf.as_slice());
+        assert_eq!(size_of_val(&*owned), size_of::<Archived<TestVersion0>>());
+
+        // Aligned vectors are kept, and others are copied into an aligned buffer
+        let ptr = buf.as_ptr();
+        let owned = unsafe { OwnedArchive::<TestParts>::from_vec(buf) };
+        assert_eq!(owned.as_bytes().as_ptr() == ptr, (ptr as usize).is_multiple_of(rkyv::AlignedVec::ALIGNMENT));
+        assert!((owned.as_bytes().as_ptr() as usize).is_multiple_of(rkyv::AlignedVec::ALIGNMENT));
+        assert_eq!(owned.a(), Some(&1));
+    }
+
+    #[cfg(feature = "validation")]
+    #[test]
+    fn owned_archive_checked() {
+        use protoss::{MetadataError, OwnedArchive, OwnedArchiveError};
+
+        #[protoss(rkyv, check_bytes)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: bool,
+            #[version = 1]
+            pub c: bool,
+        }
+
+        let test_v0 = Test::partial_v0(1, true);
+        let mut serializer = DefaultSerializer::default();
+        serializer.serialize_unsized_value(test_v0.access()).unwrap();
+        let buf = serializer.into_serializer().into_inner().to_vec();
+
+        let owned = OwnedArchive::<TestParts>::new_checked(buf.clone()).unwrap();
+        assert_eq!(owned.a(), Some(&1));
+        assert_eq!(owned.b(), Some(&true));
+        assert_eq!(owned.c(), None);
+
+        // Too short to hold the root pointer
+        assert!(matches!(
+            OwnedArchive::<TestParts>::new_checked(buf[..2].to_vec()),
+            Err(OwnedArchiveError::Metadata(MetadataError::OutOfBounds)),
+        ));
+
+        // A root pointer that points past the end of the bytes
+        let mut bad_offset = buf.clone();
+        let pos = bad_offset.len() - size_of::<rkyv::RelPtr<ArchivedTestParts>>();
+        bad_offset[pos..pos + 4].copy_from_slice(&16i32.to_le_bytes());
+        assert!(matches!(
+            OwnedArchive::<TestParts>::new_checked(bad_offset),
+            Err(OwnedArchiveError::Metadata(MetadataError::OutOfBounds)),
+        ));
+
+        // A bool that is neither 0 nor 1
+        let mut bad_bool = buf;
+        bad_bool[4] = 2;
+        assert!(matches!(
+            OwnedArchive::<TestParts>::new_checked(bad_bool),
+            Err(OwnedArchiveError::Invalid(_)),
+        ));
+    }
+
+    #[test]
+    fn archived_metadata() {
+        use core::mem::{size_of, size_of_val};
+        use protoss::{archived_metadata_for, metadata_for};
+
+        // Archived usizes are smaller than native ones, so the archived and native sizes differ
+        #[protoss(rkyv, version_encoding = "size")]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: u32,
+            #[version = 1]
+            pub b: usize,
+        }
+
+        assert_eq!(archived_metadata_for::<TestVersion0>(), size_of::<Archived<TestVersion0>>());
+        assert_eq!(archived_metadata_for::<TestVersion1>(), size_of::<Archived<Test>>());
+        assert_ne!(archived_metadata_for::<TestVersion1>(), metadata_for::<TestVersion1>());
+
+        let mut serializer = DefaultSerializer::default();
+        serializer.serialize_unsized_value(Test::partial_v1(1, 2).access()).unwrap();
+        let buf = serializer.into_serializer().into_inner();
+        let archived = unsafe { archived_unsized_root::<TestParts>(&buf) };
+        assert_eq!(size_of_val(archived), archived_metadata_for::<TestVersion1>());
+        assert_eq!(Test::version_for_archived_metadata(size_of_val(archived)), Some(1));
+        assert_eq!(Test::version_for_metadata(size_of_val(archived)), None);
+        assert_eq!(archived.b(), Some(&2));
+    }
+
+    #[test]
+    fn archived_staleness() {
+        #[protoss(rkyv)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: i32,
+            #[version = 2]
+            pub b: u32,
+        }
+
+        let mut serializer = DefaultSerializer::default();
+        serializer.serialize_unsized_value(Test::partial_v0(1).access()).unwrap();
+        let buf = serializer.into_serializer().into_inner();
+        let archived_v0 = unsafe { archived_unsized_root::<TestParts>(&buf) };
+        assert_eq!(archived_v0.version(), Some(0));
+        assert!(archived_v0.is_stale());
+
+        let mut serializer = DefaultSerializer::default();
+        serializer.serialize_unsized_value(Test::partial_v2(1, 2).access()).unwrap();
+        let buf = serializer.into_serializer().into_inner();
+        let archived_latest = unsafe { archived_unsized_root::<TestParts>(&buf) };
+        assert_eq!(archived_latest.version(), Some(2));
+        assert!(!archived_latest.is_stale());
+
+        #[protoss(rkyv, version_encoding = "size")]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct SizeInferred {
+            #[version = 0]
+            pub a: u8,
+            #[version = 1]
+            pub b: u32,
+        }
+
+        let mut serializer = DefaultSerializer::default();
+        serializer.serialize_unsized_value(SizeInferred::partial_v0(1).access()).unwrap();
+        let buf = serializer.into_serializer().into_inner();
+        let archived_v0 = unsafe { archived_unsized_root::<SizeInferredParts>(&buf) };
+        assert_eq!(archived_v0.version(), Some(0));
+        assert!(archived_v0.is_stale());
+    }
+
+    #[test]
+    fn unknown_archived_versions() {
+        use core::mem::size_of;
+        use protoss::{CheckMetadata, MetadataError};
+        use rkyv::{to_archived, ArchivePointee};
+
+        #[protoss(rkyv)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: i32,
+            #[version = 2]
+            pub b: u32,
+        }
+
+        let newer = to_archived!(3u16);
+        assert_eq!(
+            ArchivedTestParts::pointer_metadata(&newer),
+            size_of::<Archived<Test>>(),
+        );
+        assert_eq!(
+            ArchivedTestParts::check_metadata(&newer),
+            if cfg!(feature = "strict") {
+                Err(MetadataError::NewerVersion { version: 3 })
+            } else {
+                Ok(size_of::<Archived<Test>>())
+            },
+        );
+
+        let gap = to_archived!(1u16);
+        assert_eq!(ArchivedTestParts::pointer_metadata(&gap), 0);
+        assert_eq!(
+            ArchivedTestParts::check_metadata(&gap),
+            Err(MetadataError::UnknownVersion { version: 1 }),
+        );
+
+        #[protoss(rkyv, version_encoding = "size")]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct SizeInferred {
+            #[version = 0]
+            pub a: i32,
+            #[version = 1]
+            pub b: u32,
+        }
+
+        let unknown = to_archived!(6u32);
+        assert_eq!(ArchivedSizeInferredParts::pointer_metadata(&unknown), 6);
+        assert_eq!(
+            ArchivedSizeInferredParts::check_metadata(&unknown),
+            if cfg!(feature = "strict") {
+                Err(MetadataError::UnknownSize { size: 6 })
+            } else {
+                Ok(6)
+            },
+        );
+        let known = to_archived!(4u32);
+        assert_eq!(ArchivedSizeInferredParts::check_metadata(&known), Ok(4));
+    }
+
+    #[test]
+    fn checked_unsized_root() {
+        use core::mem::size_of;
+        use protoss::{checked_unsized_root, MetadataError};
+        use rkyv::{to_archived, AlignedVec, RelPtr};
+
+        #[protoss(rkyv)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: i32,
+            #[version = 2]
+            pub b: u32,
+        }
+
+        let test = Test::partial_v0(1);
+        let mut serializer = DefaultSerializer::default();
+        serializer.serialize_unsized_value(test.access()).unwrap();
+        let buf = serializer.into_serializer().into_inner();
+
+        let archived = unsafe { checked_unsized_root::<TestParts>(&buf) }.unwrap();
+        assert_eq!(archived.a(), Some(&1));
+        assert_eq!(archived.b(), None);
+
+        // The version follows the offset in the root pointer at the end of the archive
+        let at = buf.len() - size_of::<RelPtr<ArchivedTestParts>>() + size_of::<Archived<isize>>();
+        let mut gap = AlignedVec::new();
+        gap.extend_from_slice(&buf);
+        gap[at..at + 2].copy_from_slice(&to_archived!(1u16).to_ne_bytes());
+        assert_eq!(
+            unsafe { checked_unsized_root::<TestParts>(&gap) }.err(),
+            Some(MetadataError::UnknownVersion { version: 1 }),
+        );
+
+        let mut newer = AlignedVec::new();
+        newer.extend_from_slice(&buf);
+        newer[at..at + 2].copy_from_slice(&to_archived!(3u16).to_ne_bytes());
+        assert_eq!(
+            unsafe { checked_unsized_root::<TestParts>(&newer) }.err(),
+            Some(if cfg!(feature = "strict") {
+                MetadataError::NewerVersion { version: 3 }
+            } else {
+                MetadataError::OutOfBounds
+            }),
+        );
+
+        assert_eq!(
+            unsafe { checked_unsized_root::<TestParts>(&buf[..4]) }.err(),
+            Some(MetadataError::OutOfBounds),
+        );
+    }
+
+    #[test]
+    fn archived_ranges() {
+        use core::mem::size_of;
+
+        #[protoss(rkyv)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: u8,
+            #[version = 1]
+            pub c: u32,
+        }
+
+        assert_eq!(Test::archived_range_for_version(0), Some(0..size_of::<Archived<TestVersion0>>()));
+        assert_eq!(Test::archived_range_for_version(1), Some(0..size_of::<Archived<Test>>()));
+        assert_eq!(Test::archived_range_for_version(2), None);
+
+        assert_eq!(Test::archived_field_range("a"), Some(0..4));
+        assert_eq!(Test::archived_field_range("b"), Some(4..5));
+        assert_eq!(Test::archived_field_range("c"), Some(8..12));
+        assert_eq!(Test::archived_field_range("d"), None);
+    }
+
+    #[test]
+    fn root_helpers() {
+        #[protoss(rkyv, root_helpers)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 1]
+            pub c: u32,
+        }
+
+        let test_v0 = Test::partial_v0(1, 2);
+        let bytes = Test::to_archived_bytes(&test_v0).unwrap();
+        let archived = unsafe { Test::access_archived(&bytes) };
+        assert_eq!(
+            core::mem::size_of_val(archived),
+            Test::archived_range_for_version(0).unwrap().end,
+        );
+    }
+
+    #[test]
+    fn stream_serializer() {
+        use protoss::{stream_unsized_value, OwnedArchive};
+
+        #[protoss(rkyv)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: i32,
+            #[version = 1]
+            pub c: u32,
+        }
+
+        let test_v0 = Test::partial_v0(1, 2);
+
+        let mut serializer = DefaultSerializer::default();
+        let expected_pos = serializer.serialize_unsized_value(test_v0.access()).unwrap();
+        let expected = serializer.into_serializer().into_inner();
+
+        let (bytes, pos) = stream_unsized_value(test_v0.access(), Vec::new()).unwrap();
+        assert_eq!(pos, expected_pos);
+        assert_eq!(bytes.as_slice(), expected.as_slice());
+
+        let archive = unsafe { OwnedArchive::<TestParts>::from_vec(bytes) };
+        assert_eq!(
+            core::mem::size_of_val(&*archive),
+            Test::archived_range_for_version(0).unwrap().end,
+        );
+    }
+
+    #[test]
+    fn archived_accessors() {
+        #[protoss(rkyv)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: u8,
+            #[version = 1]
+            pub c: u32,
+            pub d: String,
+        }
+
+        let test_v0 = Test::partial_v0(1, 2);
+        let test_v1 = Test::partial_v1(3, 4, 5, String::from("hello world"));
+
+        for (test, version) in [(&test_v0, 0), (&test_v1, 1)] {
+            let mut serializer = DefaultSerializer::default();
+            serializer.serialize_unsized_value(test.access()).unwrap();
+            let buf = serializer.into_serializer().into_inner();
+            let archived = unsafe { rkyv::archived_unsized_root::<TestParts>(&buf) };
+
+            assert_eq!(
+                core::mem::size_of_val(archived),
+                Test::archived_range_for_version(version).unwrap().end,
+            );
+            assert_eq!(archived.a(), test.access().a());
+            assert_eq!(archived.b(), test.access().b());
+            assert_eq!(archived.c(), test.access().c());
+            assert_eq!(archived.d().map(|d| d.as_str()), test.access().d().map(|d| d.as_str()));
+        }
+    }
+
+    #[test]
+    fn legacy_as_v0() {
+        use protoss::LegacyAsV0;
+
+        #[derive(Archive, Serialize, Deserialize)]
+        #[archive_attr(repr(C))]
+        struct Legacy {
+            pub a: i32,
+            pub b: String,
+        }
+
+        #[protoss(rkyv)]
+        #[derive(Archive, Serialize, Deserialize)]
+        struct Test {
+            #[version = 0]
+            pub a: i32,
+            pub b: String,
+            #[version = 1]
+            pub c: u32,
+        }
+
+        let legacy = Legacy {
+            a: 1,
+            b: String::from("hello world"),
+        };
+        let mut serializer = DefaultSerializer::default();
+        serializer.serialize_value(&legacy).unwrap();
+        let buf = serializer.into_serializer().into_inner();
+
+        let archived = unsafe { LegacyAsV0::<Legacy>::access_root::<Test>(&buf) };
+        assert_eq!(
+            core::mem::size_of_val(archived),
+            Test::archived_range_for_version(0).unwrap().end,
+        );
+        assert_eq!(archived.a(), Some(&1));
+        assert_eq!(archived.b().map(|b| b.as_str()), Some("hello world"));
+        assert_eq!(archived.c(), None);
+    }
 }
@@ -0,0 +1,18 @@
+#[protoss::protoss]
+pub struct Example {
+    #[version = 0]
+    pub a: u32,
+    pub b: u32,
+    #[version = 1]
+    pub c: u32,
+    pub d: u32,
+}
+
+protoss::evolution_history! {
+    Example {
+        0 => [a, b],
+        1 => [c],
+    }
+}
+
+fn main() {}
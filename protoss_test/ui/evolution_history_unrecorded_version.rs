@@ -0,0 +1,16 @@
+#[protoss::protoss]
+pub struct Example {
+    #[version = 0]
+    pub a: u32,
+    pub b: u32,
+    #[version = 1]
+    pub c: u32,
+}
+
+protoss::evolution_history! {
+    Example {
+        0 => [a, b, c],
+    }
+}
+
+fn main() {}
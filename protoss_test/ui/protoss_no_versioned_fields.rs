@@ -0,0 +1,6 @@
+use protoss::protoss;
+
+#[protoss]
+struct Empty {}
+
+fn main() {}
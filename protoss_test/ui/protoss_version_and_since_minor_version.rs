@@ -0,0 +1,12 @@
+use protoss::protoss;
+
+#[protoss]
+struct Test {
+    #[version = 0]
+    a: i32,
+    #[version = 1]
+    #[field(since_minor_version = 1)]
+    b: i32,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use core::marker::PhantomData;
+use protoss::protoss;
+
+#[protoss]
+struct Test {
+    #[version = 0]
+    a: i32,
+    #[version = 1]
+    b: PhantomData<u8>,
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+#[repr(C)]
+struct ExampleV0 {
+    a: u32,
+}
+
+#[repr(C)]
+struct Example {
+    a: u32,
+    b: u32,
+}
+
+#[repr(C)]
+#[derive(ptr_meta::Pointee)]
+struct ExampleAccessor {
+    _align: [Example; 0],
+    bytes: [u8],
+}
+
+protoss::register_versions!(Example as ExampleAccessor => [ExampleV0, Example]);
+
+fn main() {}
@@ -0,0 +1,9 @@
+#[repr(C)]
+struct ExampleV0 {
+    a: u32,
+    b: u32,
+}
+
+protoss::assert_no_trailing_padding!(ExampleV0, a: u32);
+
+fn main() {}